@@ -0,0 +1,163 @@
+//! Compares the old place shifting with sparse sort keys on large queues.
+//!
+//! Needs a scratch database, every table it creates is temporary:
+//! `DATABASE_URL=postgres://... cargo bench --bench ordering`
+
+// diesel 1.x derives expand to impls nested in consts
+#![allow(non_local_definitions)]
+
+#[macro_use]
+extern crate diesel;
+
+use diesel::{prelude::*, sql_query, sql_types::BigInt, PgConnection};
+use std::{env, time::Instant};
+
+const SORT_KEY_STEP: i64 = 1 << 32;
+const OPERATIONS: i64 = 200;
+
+#[derive(QueryableByName)]
+struct Key {
+    #[sql_type = "BigInt"]
+    key: i64,
+}
+
+fn main() {
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let conn = PgConnection::establish(&database_url).expect("Error connecting to the database");
+
+    println!(
+        "{:>8} | {:>14} | {:>14} | {:>14} | {:>14}",
+        "elements", "shift insert", "sparse insert", "shift remove", "sparse remove"
+    );
+    for &size in &[1_000, 5_000, 10_000] {
+        setup(&conn, size);
+        let shift_insert = measure(|| shift_insert(&conn, size / 2));
+        let sparse_insert = measure(|| sparse_insert(&conn, size / 2));
+        let shift_remove = measure(|| shift_remove(&conn, size / 2));
+        let sparse_remove = measure(|| sparse_remove(&conn, size / 2));
+        println!(
+            "{:>8} | {:>11.3} ms | {:>11.3} ms | {:>11.3} ms | {:>11.3} ms",
+            size, shift_insert, sparse_insert, shift_remove, sparse_remove
+        );
+    }
+}
+
+/// Runs the operation `OPERATIONS` times and returns the mean time in milliseconds.
+fn measure(mut op: impl FnMut() -> QueryResult<()>) -> f64 {
+    let start = Instant::now();
+    for _ in 0..OPERATIONS {
+        op().expect("Benchmark query failed");
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / OPERATIONS as f64
+}
+
+fn setup(conn: &PgConnection, size: i64) {
+    for query in &[
+        "drop table if exists shift_elements",
+        "drop table if exists sparse_elements",
+        "create temporary table shift_elements (
+            id bigserial primary key,
+            queue_id bigint not null,
+            queue_place integer not null,
+            unique (queue_id, queue_place) deferrable initially deferred
+        )",
+        "create temporary table sparse_elements (
+            id bigserial primary key,
+            queue_id bigint not null,
+            sort_key bigint not null,
+            unique (queue_id, sort_key) deferrable initially deferred
+        )",
+    ] {
+        sql_query(*query).execute(conn).unwrap();
+    }
+
+    sql_query(
+        "insert into shift_elements (queue_id, queue_place)
+         select 1, n from generate_series(1, $1) n",
+    )
+    .bind::<BigInt, _>(size)
+    .execute(conn)
+    .unwrap();
+    sql_query(
+        "insert into sparse_elements (queue_id, sort_key)
+         select 1, n * $2 from generate_series(1, $1) n",
+    )
+    .bind::<BigInt, _>(size)
+    .bind::<BigInt, _>(SORT_KEY_STEP)
+    .execute(conn)
+    .unwrap();
+}
+
+fn shift_insert(conn: &PgConnection, place: i64) -> QueryResult<()> {
+    conn.transaction(|| {
+        sql_query(
+            "update shift_elements set queue_place = queue_place + 1
+             where queue_id = 1 and queue_place >= $1",
+        )
+        .bind::<BigInt, _>(place)
+        .execute(conn)?;
+        sql_query("insert into shift_elements (queue_id, queue_place) values (1, $1)")
+            .bind::<BigInt, _>(place)
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+fn sparse_insert(conn: &PgConnection, place: i64) -> QueryResult<()> {
+    conn.transaction(|| {
+        let keys = sql_query(
+            "select sort_key as key from sparse_elements
+             where queue_id = 1 order by sort_key offset $1 limit 2",
+        )
+        .bind::<BigInt, _>(place - 2)
+        .load::<Key>(conn)?;
+        let key = match keys.as_slice() {
+            [prev, next] if next.key - prev.key > 1 => prev.key + (next.key - prev.key) / 2,
+            _ => {
+                // the same rebalance the repository does once the keys get too close
+                sql_query(
+                    "update sparse_elements e set sort_key = r.place * $1
+                     from (select id, row_number() over (order by sort_key) as place
+                           from sparse_elements where queue_id = 1) r
+                     where r.id = e.id",
+                )
+                .bind::<BigInt, _>(SORT_KEY_STEP)
+                .execute(conn)?;
+                (place - 1) * SORT_KEY_STEP + SORT_KEY_STEP / 2
+            }
+        };
+        sql_query("insert into sparse_elements (queue_id, sort_key) values (1, $1)")
+            .bind::<BigInt, _>(key)
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+fn shift_remove(conn: &PgConnection, place: i64) -> QueryResult<()> {
+    conn.transaction(|| {
+        sql_query("delete from shift_elements where queue_id = 1 and queue_place = $1")
+            .bind::<BigInt, _>(place)
+            .execute(conn)?;
+        sql_query(
+            "update shift_elements set queue_place = queue_place - 1
+             where queue_id = 1 and queue_place > $1",
+        )
+        .bind::<BigInt, _>(place)
+        .execute(conn)?;
+        Ok(())
+    })
+}
+
+fn sparse_remove(conn: &PgConnection, place: i64) -> QueryResult<()> {
+    conn.transaction(|| {
+        sql_query(
+            "delete from sparse_elements where id = (
+                select id from sparse_elements
+                where queue_id = 1 order by sort_key offset $1 limit 1
+            )",
+        )
+        .bind::<BigInt, _>(place - 1)
+        .execute(conn)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,42 @@
+-- This file should undo anything in `up.sql`
+
+-- a queue keeps only its earliest rendered message
+
+create table old_queues (
+    id bigint,
+    chat_id bigint references chats(id),
+    qname text,
+    queue_id bigint not null,
+    primary key (id, chat_id)
+);
+
+insert into old_queues (id, chat_id, qname, queue_id)
+    select distinct on (q.id) m.message_id, q.chat_id, q.qname, q.id
+    from queues q
+    inner join queue_messages m on m.queue_id = q.id
+    order by q.id, m.message_id;
+
+alter table queue_elements drop constraint queue_elements_pkey;
+alter table queue_elements drop constraint queue_elements_queue_id_fkey;
+alter table queue_elements add column chat_id bigint references chats(id);
+
+delete from queue_elements e
+    where not exists (select 1 from old_queues q where q.queue_id = e.queue_id);
+
+update queue_elements e set queue_id = q.id, chat_id = q.chat_id
+    from old_queues q
+    where q.queue_id = e.queue_id;
+
+alter table queue_elements alter column chat_id set not null;
+
+drop table queue_messages;
+drop table queues;
+
+alter table old_queues drop column queue_id;
+alter table old_queues rename to queues;
+alter index old_queues_pkey rename to queues_pkey;
+alter table queues rename constraint old_queues_chat_id_fkey to queues_chat_id_fkey;
+
+alter table queue_elements add primary key(queue_place, queue_id, chat_id) deferrable initially deferred;
+alter table queue_elements add foreign key (queue_id, chat_id)
+    references queues(id, chat_id);
@@ -0,0 +1,44 @@
+-- Your SQL goes here
+
+-- queues get a surrogate id, rendered telegram messages are tracked separately
+
+create table new_queues (
+    id bigserial primary key,
+    chat_id bigint not null references chats(id),
+    qname text,
+    message_id bigint not null
+);
+
+insert into new_queues (chat_id, qname, message_id)
+    select chat_id, qname, id from queues;
+
+create table queue_messages (
+    message_id bigint,
+    chat_id bigint references chats(id),
+    queue_id bigint not null references new_queues(id),
+    primary key (message_id, chat_id)
+);
+
+insert into queue_messages (message_id, chat_id, queue_id)
+    select message_id, chat_id, id from new_queues;
+
+alter table queue_elements drop constraint queue_elements_pkey;
+alter table queue_elements drop constraint queue_elements_queue_id_chat_id_fkey;
+
+update queue_elements e set queue_id = q.id
+    from new_queues q
+    where q.message_id = e.queue_id and q.chat_id = e.chat_id;
+
+alter table queue_elements drop column chat_id;
+
+drop table queues;
+
+alter table new_queues drop column message_id;
+alter table new_queues rename to queues;
+alter sequence new_queues_id_seq rename to queues_id_seq;
+alter index new_queues_pkey rename to queues_pkey;
+alter table queues rename constraint new_queues_chat_id_fkey to queues_chat_id_fkey;
+
+alter table queue_elements add primary key(queue_place, queue_id) deferrable initially deferred;
+alter table queue_elements add foreign key (queue_id)
+    references queues(id);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+alter table queue_elements drop constraint queue_elements_place_key;
+
+alter table queue_elements drop column id;
+
+alter table queue_elements add primary key(queue_place, queue_id) deferrable initially deferred;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+-- elements get an identity independent from their place
+
+alter table queue_elements drop constraint queue_elements_pkey;
+
+alter table queue_elements add column id bigserial primary key;
+
+alter table queue_elements add constraint queue_elements_place_key
+    unique (queue_id, queue_place) deferrable initially deferred;
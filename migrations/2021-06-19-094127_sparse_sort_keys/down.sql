@@ -0,0 +1,17 @@
+-- This file should undo anything in `up.sql`
+
+alter table queue_elements drop constraint queue_elements_sort_key_key;
+
+update queue_elements e set sort_key = p.place
+    from (
+        select id, row_number() over (partition by queue_id order by sort_key) as place
+        from queue_elements
+    ) p
+    where p.id = e.id;
+
+alter table queue_elements alter column sort_key type integer;
+
+alter table queue_elements rename column sort_key to queue_place;
+
+alter table queue_elements add constraint queue_elements_place_key
+    unique (queue_id, queue_place) deferrable initially deferred;
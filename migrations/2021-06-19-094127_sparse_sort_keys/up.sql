@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+-- places are computed on read, elements are ordered by sparse keys
+-- so an insert or a removal doesn't have to shift the rest of the queue
+
+alter table queue_elements drop constraint queue_elements_place_key;
+
+alter table queue_elements rename column queue_place to sort_key;
+
+alter table queue_elements alter column sort_key type bigint;
+
+update queue_elements set sort_key = sort_key * 4294967296;
+
+alter table queue_elements add constraint queue_elements_sort_key_key
+    unique (queue_id, sort_key) deferrable initially deferred;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+alter table queues add column version integer not null default 0;
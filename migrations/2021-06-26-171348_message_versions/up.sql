@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- the version each message shows, commands replying to it are checked against it
+alter table queue_messages add column version integer not null default 0;
+
+update queue_messages m set version = q.version from queues q where q.id = m.queue_id;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+-- long queues are rendered page by page, each message remembers its page
+
+alter table queue_messages add column page integer not null default 0;
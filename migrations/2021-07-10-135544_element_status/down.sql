@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+alter table queue_elements drop column user_id;
+
+alter table queue_elements drop column status;
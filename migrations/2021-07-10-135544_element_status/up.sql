@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+alter table queue_elements add column status text not null default 'waiting'
+    check (status in ('waiting', 'current', 'done'));
+
+-- set for elements which stand for a telegram user
+alter table queue_elements add column user_id bigint;
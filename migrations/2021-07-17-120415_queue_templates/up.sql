@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+-- how queues of a chat are rendered, one row per chat
+create table queue_templates (
+    chat_id bigint primary key references chats (id) on delete cascade,
+    numbering text not null default 'paren'
+        check (numbering in ('paren', 'dot', 'hash', 'none')),
+    waiting_mark text not null default '',
+    current_mark text not null default '▶',
+    done_mark text not null default '',
+    -- `{name}` is replaced with the name of the queue
+    header text not null default '{name}',
+    footer text not null default '',
+    show_counts boolean not null default false
+);
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+create table chat_settings (
+    chat_id bigint primary key references chats (id) on delete cascade,
+    -- pin the messages of newly created queues
+    auto_pin boolean not null default true,
+    -- send messages without a notification
+    silent boolean not null default false,
+    -- reply to commands which changed a queue
+    confirmations boolean not null default true,
+    -- how /queuefile orders the lines of a file
+    shuffle text not null default 'keep' check (shuffle in ('keep', 'shuffle')),
+    language text not null default 'en' check (language in ('en', 'uk'))
+);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+update chat_settings set language = 'en' where language is null;
+alter table chat_settings alter column language set not null;
+alter table chat_settings alter column language set default 'en';
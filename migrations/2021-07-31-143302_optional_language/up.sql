@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- null means each user gets the language of their telegram client
+alter table chat_settings alter column language drop default;
+alter table chat_settings alter column language drop not null;
+update chat_settings set language = null where language = 'en';
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+drop index queues_chat_id_created_at_idx;
+
+alter table queues drop column archived;
+
+alter table queues drop column created_at;
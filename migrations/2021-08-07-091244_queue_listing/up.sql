@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+-- queues which existed before get the time of the migration
+alter table queues add column created_at timestamptz not null default now();
+
+-- archived queues are kept but hidden from /list
+alter table queues add column archived boolean not null default false;
+
+create index queues_chat_id_created_at_idx on queues (chat_id, created_at);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+-- deleting a queue takes its elements and messages with it
+alter table queue_elements drop constraint queue_elements_queue_id_fkey;
+alter table queue_elements add constraint queue_elements_queue_id_fkey
+    foreign key (queue_id) references queues(id) on delete cascade;
+
+alter table queue_messages drop constraint queue_messages_queue_id_fkey;
+alter table queue_messages add constraint queue_messages_queue_id_fkey
+    foreign key (queue_id) references queues(id) on delete cascade;
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+
+alter table chat_settings drop column expiry_action;
+alter table chat_settings drop column expiry_days;
+
+drop trigger set_updated_at on queue_elements;
+alter table queue_elements drop column updated_at;
+alter table queue_elements drop column created_at;
+
+drop trigger set_updated_at on queues;
+alter table queues drop column updated_at;
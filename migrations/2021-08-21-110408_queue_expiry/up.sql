@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+alter table queues add column updated_at timestamptz not null default now();
+select diesel_manage_updated_at('queues');
+
+alter table queue_elements add column created_at timestamptz not null default now();
+alter table queue_elements add column updated_at timestamptz not null default now();
+select diesel_manage_updated_at('queue_elements');
+
+-- queues without changes for this many days are expired, never if null
+alter table chat_settings add column expiry_days integer check (expiry_days > 0);
+alter table chat_settings add column expiry_action text not null default 'archive'
+    check (expiry_action in ('archive', 'delete'));
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+-- queues created every week from the elements of another queue
+create table scheduled_queues (
+    id bigserial primary key,
+    chat_id bigint not null references chats (id) on delete cascade,
+    source_queue_id bigint not null references queues (id) on delete cascade,
+    -- {n} is replaced with next_number
+    qname text not null,
+    next_number integer not null default 1,
+    next_run_at timestamptz not null
+);
+
+create index scheduled_queues_next_run_at on scheduled_queues (next_run_at);
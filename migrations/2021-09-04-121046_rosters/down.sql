@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+
+delete from scheduled_queues where source_queue_id is null;
+alter table scheduled_queues drop constraint scheduled_queues_source_check;
+alter table scheduled_queues drop column roster_id;
+alter table scheduled_queues alter column source_queue_id set not null;
+
+drop table roster_members;
+drop table rosters;
@@ -0,0 +1,26 @@
+-- Your SQL goes here
+
+-- named lists of people kept by a chat to create queues from
+create table rosters (
+    id bigserial primary key,
+    chat_id bigint not null references chats (id) on delete cascade,
+    name text not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now(),
+    unique (chat_id, name)
+);
+select diesel_manage_updated_at('rosters');
+
+create table roster_members (
+    roster_id bigint not null references rosters (id) on delete cascade,
+    position integer not null,
+    element_name text not null,
+    user_id bigint,
+    primary key (roster_id, position)
+);
+
+-- schedules create queues either from a queue or from a roster
+alter table scheduled_queues alter column source_queue_id drop not null;
+alter table scheduled_queues add column roster_id bigint references rosters (id) on delete cascade;
+alter table scheduled_queues add constraint scheduled_queues_source_check
+    check ((source_queue_id is null) <> (roster_id is null));
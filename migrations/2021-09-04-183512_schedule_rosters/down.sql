@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+-- the copied rosters are kept, the schedules go on using them
+alter table scheduled_queues alter column roster_id drop not null;
+alter table scheduled_queues add column source_queue_id bigint references queues (id) on delete cascade;
+alter table scheduled_queues add constraint scheduled_queues_source_check
+    check ((source_queue_id is null) <> (roster_id is null));
@@ -0,0 +1,26 @@
+-- Your SQL goes here
+
+-- schedules keep their own copy of the people, so deleting the source queue doesn't end them
+do $$
+declare
+    schedule record;
+    new_roster bigint;
+begin
+    for schedule in select * from scheduled_queues where source_queue_id is not null loop
+        insert into rosters (chat_id, name)
+            values (schedule.chat_id, trim(replace(schedule.qname, '{n}', '')) || ' (' || schedule.id || ')')
+            returning id into new_roster;
+        insert into roster_members (roster_id, position, element_name, user_id)
+            select new_roster, row_number() over (order by sort_key), element_name, user_id
+            from queue_elements
+            -- the free slots of a sheet are nobody
+            where queue_id = schedule.source_queue_id and (user_id is not null or element_name <> '');
+        update scheduled_queues
+            set roster_id = new_roster, source_queue_id = null
+            where id = schedule.id;
+    end loop;
+end $$;
+
+alter table scheduled_queues drop constraint scheduled_queues_source_check;
+alter table scheduled_queues drop column source_queue_id;
+alter table scheduled_queues alter column roster_id set not null;
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+
+-- messages collecting people for a queue until a deadline
+create table signups (
+    id bigserial primary key,
+    chat_id bigint not null references chats (id) on delete cascade,
+    -- the message with the sign-up buttons, set once it is sent
+    message_id bigint,
+    qname text,
+    shuffle boolean not null default false,
+    closes_at timestamptz not null,
+    closed boolean not null default false
+);
+
+create index signups_closes_at on signups (closes_at) where not closed;
+
+create table signup_entries (
+    signup_id bigint not null references signups (id) on delete cascade,
+    user_id bigint not null,
+    element_name text not null,
+    signed_up_at timestamptz not null default now(),
+    primary key (signup_id, user_id)
+);
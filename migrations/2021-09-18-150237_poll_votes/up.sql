@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+-- the latest answer of every user to the non-anonymous polls sent by the bot,
+-- telegram only reports votes in polls the bot sent itself
+create table poll_votes (
+    poll_id text not null,
+    user_id bigint not null,
+    element_name text not null,
+    -- zero-based, as telegram numbers the options
+    option_ids integer[] not null,
+    voted_at timestamptz not null default now(),
+    primary key (poll_id, user_id)
+);
@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+
+alter table queue_templates drop column absent_mark;
+alter table queue_templates drop column skipped_mark;
+
+update queue_elements set status = 'waiting' where status in ('skipped', 'absent');
+alter table queue_elements drop constraint queue_elements_status_check;
+alter table queue_elements add constraint queue_elements_status_check
+    check (status in ('waiting', 'current', 'done'));
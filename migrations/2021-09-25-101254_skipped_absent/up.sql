@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+-- skipped elements are called again later, absent ones are shown but never called
+alter table queue_elements drop constraint queue_elements_status_check;
+alter table queue_elements add constraint queue_elements_status_check
+    check (status in ('waiting', 'current', 'done', 'skipped', 'absent'));
+
+alter table queue_templates add column skipped_mark text not null default '↷';
+alter table queue_templates add column absent_mark text not null default '✗';
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+-- asks to trade places with the element of another user, which has to agree
+create table swap_requests (
+    id bigserial primary key,
+    queue_id bigint not null references queues (id) on delete cascade,
+    chat_id bigint not null references chats (id) on delete cascade,
+    -- the message with the accept buttons, set once it is sent
+    message_id bigint,
+    -- the element of the user who asks and the one they want to trade with
+    from_element_id bigint not null references queue_elements (id) on delete cascade,
+    to_element_id bigint not null references queue_elements (id) on delete cascade,
+    expires_at timestamptz not null,
+    check (from_element_id <> to_element_id)
+);
+
+create index swap_requests_expires_at on swap_requests (expires_at);
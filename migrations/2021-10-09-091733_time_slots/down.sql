@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+alter table queues drop constraint queues_slots_check;
+alter table queues drop column utc_offset;
+alter table queues drop column slot_minutes;
+alter table queues drop column starts_at;
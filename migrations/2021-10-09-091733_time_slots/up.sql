@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+-- elements of a queue with slots are expected one after another from `starts_at`
+alter table queues add column starts_at timestamptz;
+alter table queues add column slot_minutes integer check (slot_minutes > 0);
+-- seconds east of UTC the start was given in, the times are shown in it
+alter table queues add column utc_offset integer not null default 0;
+alter table queues add constraint queues_slots_check
+    check ((starts_at is null) = (slot_minutes is null));
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+alter table queue_elements drop column label;
+alter table queues drop column sheet;
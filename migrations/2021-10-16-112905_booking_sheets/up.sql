@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- the elements of a sheet are slots which users claim, a free slot has no user and no name
+alter table queues add column sheet boolean not null default false;
+-- the time or topic of a slot, null for numbered slots and ordinary elements
+alter table queue_elements add column label text;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+-- how many times two people were paired by /pairs, people are `u<user id>` or `n<name>`
+create table pair_history (
+    chat_id bigint not null references chats (id) on delete cascade,
+    first_key text not null,
+    second_key text not null,
+    times integer not null default 1,
+    primary key (chat_id, first_key, second_key),
+    check (first_key < second_key)
+);
@@ -0,0 +1,128 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+/// Options of `/assign`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AssignArgs {
+    /// The name of the new queue, the queue of people gives it otherwise.
+    pub name: Option<String>,
+    /// Gives every topic to one person at most.
+    pub unique: bool,
+    /// Makes the assignment repeatable for the same lists.
+    pub seed: Option<u64>,
+}
+
+/// Parses `[unique] [seed=N] [name]`, the words which aren't options make up the name.
+pub fn parse(arg: &str) -> Option<AssignArgs> {
+    let mut args = AssignArgs::default();
+    let mut name = Vec::new();
+    for word in arg.split_whitespace() {
+        if word == "unique" {
+            args.unique = true;
+        } else if let Some(seed) = word.strip_prefix("seed=") {
+            args.seed = Some(seed.parse().ok()?);
+        } else {
+            name.push(word);
+        }
+    }
+    if !name.is_empty() {
+        args.name = Some(name.join(" "));
+    }
+    Some(args)
+}
+
+/// Picks a topic for each of `people` in turn. Topics are shuffled and handed out
+/// round-robin, so they are used evenly when there are more people than topics.
+/// Returns `None` if topics have to be unique and there aren't enough of them.
+pub fn assign(people: usize, mut topics: Vec<String>, args: &AssignArgs) -> Option<Vec<String>> {
+    if topics.is_empty() || (args.unique && people > topics.len()) {
+        return None;
+    }
+
+    match args.seed {
+        Some(seed) => topics.shuffle(&mut StdRng::seed_from_u64(seed)),
+        None => topics.shuffle(&mut rand::rngs::OsRng),
+    }
+    Some(
+        (0..people)
+            .map(|i| topics[i % topics.len()].clone())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topics(count: usize) -> Vec<String> {
+        (0..count).map(|x| format!("topic {}", x)).collect()
+    }
+
+    fn seeded(seed: u64) -> AssignArgs {
+        AssignArgs {
+            seed: Some(seed),
+            ..AssignArgs::default()
+        }
+    }
+
+    #[test]
+    fn parses_options_and_name() {
+        assert_eq!(
+            parse("unique seed=42 Lab 3"),
+            Some(AssignArgs {
+                name: Some("Lab 3".to_string()),
+                unique: true,
+                seed: Some(42),
+            })
+        );
+        assert_eq!(parse(""), Some(AssignArgs::default()));
+    }
+
+    #[test]
+    fn rejects_bad_seeds() {
+        assert_eq!(parse("seed="), None);
+        assert_eq!(parse("seed=abc"), None);
+        assert_eq!(parse("seed=-1"), None);
+        assert_eq!(parse("unique seed=1.5 Lab"), None);
+    }
+
+    #[test]
+    fn same_seed_gives_same_topics() {
+        let first = assign(7, topics(5), &seeded(42)).unwrap();
+        assert_eq!(assign(7, topics(5), &seeded(42)).unwrap(), first);
+
+        // some seed orders the topics differently
+        assert!((0..10).any(|seed| assign(7, topics(5), &seeded(seed)).unwrap() != first));
+    }
+
+    #[test]
+    fn unique_topics_need_enough_of_them() {
+        let unique = AssignArgs {
+            unique: true,
+            ..AssignArgs::default()
+        };
+        assert_eq!(assign(4, topics(3), &unique), None);
+
+        let mut assigned = assign(3, topics(3), &unique).unwrap();
+        assigned.sort();
+        assert_eq!(assigned, topics(3));
+    }
+
+    #[test]
+    fn round_robin_uses_topics_evenly() {
+        for people in 0..20 {
+            let assigned = assign(people, topics(6), &AssignArgs::default()).unwrap();
+            assert_eq!(assigned.len(), people);
+            let counts = topics(6)
+                .iter()
+                .map(|topic| assigned.iter().filter(|x| *x == topic).count())
+                .collect::<Vec<_>>();
+            let (min, max) = (counts.iter().min(), counts.iter().max());
+            assert!(max.unwrap() - min.unwrap() <= 1);
+        }
+    }
+
+    #[test]
+    fn nothing_to_assign_without_topics() {
+        assert_eq!(assign(3, Vec::new(), &AssignArgs::default()), None);
+    }
+}
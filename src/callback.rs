@@ -0,0 +1,151 @@
+use std::{fmt, str::FromStr};
+
+use crate::settings::Setting;
+
+/// Data attached to inline keyboard buttons.
+/// Telegram limits it to 64 bytes, so it is kept short.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallbackData {
+    /// Runs the command which the button's message replies to,
+    /// as long as the queue is still at `version`.
+    Confirm {
+        queue_id: i64,
+        version: i32,
+    },
+    Cancel,
+    /// Shows another page of the queue in the button's message.
+    Page {
+        queue_id: i64,
+        page: i32,
+    },
+    /// Changes a setting of the button's chat.
+    Setting(Setting),
+    /// Shows another page of `/list` in the button's message.
+    List {
+        page: i64,
+    },
+    /// Posts a listed queue again.
+    Repost {
+        queue_id: i64,
+    },
+    /// Archives a listed queue and refreshes the list at `page`.
+    Archive {
+        queue_id: i64,
+        page: i64,
+    },
+    /// Cancels a schedule listed by `/schedule`.
+    Unschedule {
+        schedule_id: i64,
+    },
+    /// Adds the user to an open `/signup`.
+    SignUp {
+        signup_id: i64,
+    },
+    /// Takes the user off an open `/signup`.
+    LeaveSignup {
+        signup_id: i64,
+    },
+    /// Trades places as asked by `/trade`, only for the owner of the other element.
+    AcceptSwap {
+        request_id: i64,
+    },
+    /// Turns down a request of `/trade`, or takes it back.
+    DeclineSwap {
+        request_id: i64,
+    },
+    /// Gives a free slot of a sheet to the user.
+    Claim {
+        queue_id: i64,
+        element_id: i64,
+    },
+    /// Frees the slot of the user in a sheet.
+    Release {
+        queue_id: i64,
+    },
+}
+
+impl fmt::Display for CallbackData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallbackData::Confirm { queue_id, version } => {
+                write!(f, "confirm {} {}", queue_id, version)
+            }
+            CallbackData::Cancel => write!(f, "cancel"),
+            CallbackData::Page { queue_id, page } => write!(f, "page {} {}", queue_id, page),
+            CallbackData::Setting(setting) => write!(f, "setting {}", setting),
+            CallbackData::List { page } => write!(f, "list {}", page),
+            CallbackData::Repost { queue_id } => write!(f, "repost {}", queue_id),
+            CallbackData::Archive { queue_id, page } => {
+                write!(f, "archive {} {}", queue_id, page)
+            }
+            CallbackData::Unschedule { schedule_id } => write!(f, "unschedule {}", schedule_id),
+            CallbackData::SignUp { signup_id } => write!(f, "signup {}", signup_id),
+            CallbackData::LeaveSignup { signup_id } => write!(f, "leave {}", signup_id),
+            CallbackData::AcceptSwap { request_id } => write!(f, "accept {}", request_id),
+            CallbackData::DeclineSwap { request_id } => write!(f, "decline {}", request_id),
+            CallbackData::Claim {
+                queue_id,
+                element_id,
+            } => write!(f, "claim {} {}", queue_id, element_id),
+            CallbackData::Release { queue_id } => write!(f, "release {}", queue_id),
+        }
+    }
+}
+
+impl FromStr for CallbackData {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut args = s.split_whitespace();
+
+        match args.next() {
+            Some("confirm") => Ok(CallbackData::Confirm {
+                queue_id: next_arg(&mut args)?,
+                version: next_arg(&mut args)?,
+            }),
+            Some("cancel") => Ok(CallbackData::Cancel),
+            Some("page") => Ok(CallbackData::Page {
+                queue_id: next_arg(&mut args)?,
+                page: next_arg(&mut args)?,
+            }),
+            Some("setting") => Ok(CallbackData::Setting(next_arg(&mut args)?)),
+            Some("list") => Ok(CallbackData::List {
+                page: next_arg(&mut args)?,
+            }),
+            Some("repost") => Ok(CallbackData::Repost {
+                queue_id: next_arg(&mut args)?,
+            }),
+            Some("archive") => Ok(CallbackData::Archive {
+                queue_id: next_arg(&mut args)?,
+                page: next_arg(&mut args)?,
+            }),
+            Some("unschedule") => Ok(CallbackData::Unschedule {
+                schedule_id: next_arg(&mut args)?,
+            }),
+            Some("signup") => Ok(CallbackData::SignUp {
+                signup_id: next_arg(&mut args)?,
+            }),
+            Some("leave") => Ok(CallbackData::LeaveSignup {
+                signup_id: next_arg(&mut args)?,
+            }),
+            Some("accept") => Ok(CallbackData::AcceptSwap {
+                request_id: next_arg(&mut args)?,
+            }),
+            Some("decline") => Ok(CallbackData::DeclineSwap {
+                request_id: next_arg(&mut args)?,
+            }),
+            Some("claim") => Ok(CallbackData::Claim {
+                queue_id: next_arg(&mut args)?,
+                element_id: next_arg(&mut args)?,
+            }),
+            Some("release") => Ok(CallbackData::Release {
+                queue_id: next_arg(&mut args)?,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+fn next_arg<'a, T: FromStr>(args: &mut impl Iterator<Item = &'a str>) -> Result<T, ()> {
+    args.next().ok_or(())?.parse().map_err(|_| ())
+}
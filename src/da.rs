@@ -1,6 +1,9 @@
 mod error;
+// diesel 1.x derives expand to impls nested in consts
+#[allow(non_local_definitions)]
 mod models;
 mod repo;
+#[allow(non_local_definitions)]
 mod schema;
 
 pub use error::Error;
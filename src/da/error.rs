@@ -6,6 +6,14 @@ pub enum Error {
     Diesel(#[from] diesel::result::Error),
     #[error("Nonexistent position for swap.")]
     NonexistentPosition { pos: i32 },
+    #[error("Nonexistent element.")]
+    NonexistentElement { id: i64 },
+    #[error("The sign-up is closed.")]
+    SignupClosed,
+    #[error("The slot is taken.")]
+    SlotTaken,
+    #[error("The user already has a slot.")]
+    AlreadyClaimed,
     #[error("Something unexpected.")]
     Wtf(String),
 }
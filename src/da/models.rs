@@ -1,3 +1,12 @@
+use chrono::{DateTime, Utc};
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::Pg,
+    serialize::{self, Output, ToSql},
+    sql_types::{BigInt, Nullable, Text},
+};
+use std::{io::Write, str::FromStr};
+
 use super::schema::*;
 
 #[derive(Queryable, Insertable, Clone, Debug)]
@@ -6,51 +15,485 @@ pub struct Chat {
     pub id: i64,
 }
 
-#[derive(Queryable, Insertable, Clone, Debug)]
-#[table_name = "queues"]
+#[derive(Queryable, Clone, Debug)]
 pub struct Queue {
     pub id: i64,
     pub chat_id: i64,
     pub qname: Option<String>,
+    /// Incremented on every modification of the queue.
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    /// Archived queues are kept but hidden from listings.
+    pub archived: bool,
+    pub updated_at: DateTime<Utc>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub slot_minutes: Option<i32>,
+    /// Seconds east of UTC, the slot times are shown in it.
+    pub utc_offset: i32,
+    /// Users claim the free elements of a sheet instead of queueing up.
+    pub sheet: bool,
 }
 
 impl Queue {
     pub fn key(&self) -> QueueKey {
-        QueueKey {
-            id: self.id,
-            chat_id: self.chat_id,
-        }
+        QueueKey { id: self.id }
     }
+
+    pub fn slots(&self) -> Option<Slots> {
+        Some(Slots {
+            starts_at: self.starts_at?,
+            minutes: self.slot_minutes?,
+            utc_offset: self.utc_offset,
+        })
+    }
+}
+
+/// Fixed-length time slots, one per element in the queue order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slots {
+    pub starts_at: DateTime<Utc>,
+    pub minutes: i32,
+    pub utc_offset: i32,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "queues"]
+pub struct NewQueue {
+    pub chat_id: i64,
+    pub qname: Option<String>,
 }
 
 #[derive(Queryable, Clone, Debug)]
 pub struct QueueKey {
     pub id: i64,
-    pub chat_id: i64,
 }
 
+/// A queue as it is shown in `/list`.
+#[derive(Clone, Debug)]
+pub struct QueueSummary {
+    pub queue: Queue,
+    pub element_count: i64,
+    /// The latest message rendering the queue.
+    pub message_id: Option<i64>,
+}
+
+/// Aggregates of a queue computed for `QueueSummary`.
+#[derive(QueryableByName, Clone, Debug)]
+pub struct QueueStats {
+    #[sql_type = "BigInt"]
+    pub queue_id: i64,
+    #[sql_type = "BigInt"]
+    pub element_count: i64,
+    #[sql_type = "Nullable<BigInt>"]
+    pub message_id: Option<i64>,
+}
+
+/// A telegram message which renders a queue.
 #[derive(Queryable, Insertable, Clone, Debug)]
-#[table_name = "queue_elements"]
+#[table_name = "queue_messages"]
+pub struct QueueMessage {
+    pub message_id: i64,
+    pub chat_id: i64,
+    pub queue_id: i64,
+    /// The version of the queue the message shows.
+    pub version: i32,
+    /// Zero-based page of the queue shown in the message.
+    pub page: i32,
+}
+
+#[derive(Queryable, Clone, Debug)]
 pub struct QueueElement {
     pub element_name: String,
+    #[allow(dead_code)]
     pub queue_id: i64,
-    pub chat_id: i64,
+    pub sort_key: i64,
+    pub id: i64,
+    pub status: ElementStatus,
+    pub user_id: Option<i64>,
+    #[allow(dead_code)]
+    pub created_at: DateTime<Utc>,
+    #[allow(dead_code)]
+    pub updated_at: DateTime<Utc>,
+    pub label: Option<String>,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "queue_elements"]
+pub struct NewQueueElement {
+    pub element_name: String,
+    pub queue_id: i64,
+    pub sort_key: i64,
+    pub user_id: Option<i64>,
+    pub label: Option<String>,
+}
+
+/// An element as it is shown in the queue, `queue_place` is computed on read.
+#[derive(Clone, Debug)]
+pub struct QueueElementForQueue {
+    pub element_name: String,
     pub queue_place: i32,
+    pub id: i64,
+    pub status: ElementStatus,
+    pub user_id: Option<i64>,
+    pub label: Option<String>,
+}
+
+impl QueueElementForQueue {
+    /// Whether the element is a slot of a sheet which nobody claimed yet.
+    /// Elements of other queues are never free, even with an empty name.
+    pub fn is_free_slot(&self, queue: &Queue) -> bool {
+        queue.sheet && self.user_id.is_none() && self.element_name.is_empty()
+    }
+}
+
+/// A free slot of a new sheet.
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "queue_elements"]
+pub struct NewSheetSlot {
+    pub element_name: String,
+    pub queue_id: i64,
+    pub sort_key: i64,
+    pub label: Option<String>,
 }
 
-impl QueueElement {
-    pub fn from_parts(queue: QueueKey, element: QueueElementForQueue) -> Self {
-        QueueElement {
+/// What a new element is made of, its queue and place are decided on insertion.
+#[derive(Clone, Debug)]
+pub struct ElementData {
+    pub element_name: String,
+    /// The telegram user the element stands for.
+    pub user_id: Option<i64>,
+}
+
+impl ElementData {
+    pub fn named(element_name: String) -> Self {
+        ElementData {
+            element_name,
+            user_id: None,
+        }
+    }
+}
+
+impl From<QueueElementForQueue> for ElementData {
+    fn from(element: QueueElementForQueue) -> Self {
+        ElementData {
             element_name: element.element_name,
-            queue_id: queue.id,
-            chat_id: queue.chat_id,
-            queue_place: element.queue_place,
+            user_id: element.user_id,
         }
     }
 }
 
+/// Implements the `text` column mapping of a fieldless enum
+/// together with its `FromStr` and `as_str`, using the same names for both.
+macro_rules! text_enum {
+    ($name:ident { $($variant:ident => $text:literal),+ $(,)? }) => {
+        impl $name {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $text,)+
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($text => Ok($name::$variant),)+
+                    s => Err(format!("Unknown {}: {}", stringify!($name), s)),
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for $name {
+            fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+                ToSql::<Text, Pg>::to_sql(self.as_str(), out)
+            }
+        }
+
+        impl FromSql<Text, Pg> for $name {
+            fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+                Ok(<String as FromSql<Text, Pg>>::from_sql(bytes)?.parse()?)
+            }
+        }
+    };
+}
+
+#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq)]
+#[sql_type = "Text"]
+pub enum ElementStatus {
+    Waiting,
+    /// The element which is being served right now, at most one per queue.
+    Current,
+    Done,
+    /// Moved back by `/skip`, called again when its turn comes.
+    Skipped,
+    /// Shown in the queue but never called.
+    Absent,
+}
+
+text_enum!(ElementStatus {
+    Waiting => "waiting",
+    Current => "current",
+    Done => "done",
+    Skipped => "skipped",
+    Absent => "absent",
+});
+
+/// Points to an element either by its current place or by its stable id.
+#[derive(Clone, Copy, Debug)]
+pub enum ElementRef {
+    Place(i32),
+    Id(i64),
+}
+
+/// How the queues of a chat are rendered.
+#[derive(Queryable, AsChangeset, Clone, Debug)]
+#[table_name = "queue_templates"]
+#[primary_key(chat_id)]
+pub struct QueueTemplate {
+    pub chat_id: i64,
+    pub numbering: Numbering,
+    /// Shown before the elements of each status, empty for nothing.
+    pub waiting_mark: String,
+    pub current_mark: String,
+    pub done_mark: String,
+    /// `{name}` is replaced with the queue name, the line is left out for unnamed queues.
+    pub header: String,
+    pub footer: String,
+    /// Whether to show how many elements are in the queue and how many are done.
+    pub show_counts: bool,
+    pub skipped_mark: String,
+    pub absent_mark: String,
+}
+
+impl QueueTemplate {
+    pub fn mark(&self, status: ElementStatus) -> &str {
+        match status {
+            ElementStatus::Waiting => &self.waiting_mark,
+            ElementStatus::Current => &self.current_mark,
+            ElementStatus::Done => &self.done_mark,
+            ElementStatus::Skipped => &self.skipped_mark,
+            ElementStatus::Absent => &self.absent_mark,
+        }
+    }
+}
+
+#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq)]
+#[sql_type = "Text"]
+pub enum Numbering {
+    /// `1) name`
+    Paren,
+    /// `1. name`
+    Dot,
+    /// `#1 name`
+    Hash,
+    /// No places at all.
+    Hidden,
+}
+
+text_enum!(Numbering {
+    Paren => "paren",
+    Dot => "dot",
+    Hash => "hash",
+    Hidden => "none",
+});
+
+/// Per-chat behaviour of the bot.
+#[derive(Queryable, AsChangeset, Clone, Debug)]
+#[table_name = "chat_settings"]
+#[primary_key(chat_id)]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct ChatSettings {
+    pub chat_id: i64,
+    /// Pin the messages of newly created queues.
+    pub auto_pin: bool,
+    /// Send messages without a notification.
+    pub silent: bool,
+    /// Reply to commands which changed a queue.
+    pub confirmations: bool,
+    /// How `/queuefile` orders the lines of a file.
+    pub shuffle: ShuffleMode,
+    /// `None` leaves every user with the language of their telegram client.
+    pub language: Option<Language>,
+    /// Queues without changes for this many days are expired, never if `None`.
+    pub expiry_days: Option<i32>,
+    pub expiry_action: ExpiryAction,
+}
+
+#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq)]
+#[sql_type = "Text"]
+pub enum ExpiryAction {
+    Archive,
+    Delete,
+}
+
+text_enum!(ExpiryAction {
+    Archive => "archive",
+    Delete => "delete",
+});
+
+/// A queue left without changes for longer than its chat allows.
+#[derive(QueryableByName, Clone, Debug)]
+pub struct ExpiredQueue {
+    #[sql_type = "BigInt"]
+    pub queue_id: i64,
+}
+
+/// A queue created every week from the members of a roster.
 #[derive(Queryable, Clone, Debug)]
-pub struct QueueElementForQueue {
+pub struct ScheduledQueue {
+    pub id: i64,
+    pub chat_id: i64,
+    /// The name of created queues, `{n}` is replaced with `next_number`.
+    pub qname: String,
+    pub next_number: i32,
+    pub next_run_at: DateTime<Utc>,
+    pub roster_id: i64,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "scheduled_queues"]
+pub struct NewScheduledQueue {
+    pub chat_id: i64,
+    /// A schedule of a queue gets a copy of its people as a roster of its own.
+    pub roster_id: i64,
+    pub qname: String,
+    pub next_run_at: DateTime<Utc>,
+}
+
+/// A named list of people kept by a chat to create queues from.
+#[derive(Queryable, Clone, Debug)]
+pub struct Roster {
+    pub id: i64,
+    #[allow(dead_code)]
+    pub chat_id: i64,
+    pub name: String,
+    #[allow(dead_code)]
+    pub created_at: DateTime<Utc>,
+    #[allow(dead_code)]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "roster_members"]
+pub struct NewRosterMember {
+    pub roster_id: i64,
+    pub position: i32,
     pub element_name: String,
-    pub queue_place: i32,
+    pub user_id: Option<i64>,
 }
+
+/// A message collecting people for a queue until `closes_at`.
+#[derive(Queryable, Clone, Debug)]
+pub struct Signup {
+    pub id: i64,
+    pub chat_id: i64,
+    pub message_id: Option<i64>,
+    pub qname: Option<String>,
+    pub shuffle: bool,
+    pub closes_at: DateTime<Utc>,
+    #[allow(dead_code)]
+    pub closed: bool,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "signups"]
+pub struct NewSignup {
+    pub chat_id: i64,
+    pub qname: Option<String>,
+    pub shuffle: bool,
+    pub closes_at: DateTime<Utc>,
+}
+
+#[derive(Queryable, Clone, Debug)]
+pub struct SignupEntry {
+    #[allow(dead_code)]
+    pub signup_id: i64,
+    pub user_id: i64,
+    pub element_name: String,
+    #[allow(dead_code)]
+    pub signed_up_at: DateTime<Utc>,
+}
+
+impl From<SignupEntry> for ElementData {
+    fn from(entry: SignupEntry) -> Self {
+        ElementData {
+            element_name: entry.element_name,
+            user_id: Some(entry.user_id),
+        }
+    }
+}
+
+/// The latest answer of a user to a poll sent by the bot.
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "poll_votes"]
+pub struct NewPollVote {
+    pub poll_id: String,
+    pub user_id: i64,
+    pub element_name: String,
+    /// Zero-based, empty when the vote is retracted.
+    pub option_ids: Vec<i32>,
+}
+
+/// Asks the owner of `to_element_id` to trade places with `from_element_id`.
+#[derive(Queryable, Clone, Debug)]
+pub struct SwapRequest {
+    pub id: i64,
+    pub queue_id: i64,
+    pub chat_id: i64,
+    pub message_id: Option<i64>,
+    pub from_element_id: i64,
+    pub to_element_id: i64,
+    #[allow(dead_code)]
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SwapRequest {
+    pub fn queue_key(&self) -> QueueKey {
+        QueueKey { id: self.queue_id }
+    }
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "swap_requests"]
+pub struct NewSwapRequest {
+    pub queue_id: i64,
+    pub chat_id: i64,
+    pub from_element_id: i64,
+    pub to_element_id: i64,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A roster as it is shown in `/roster list`.
+#[derive(QueryableByName, Clone, Debug)]
+pub struct RosterSummary {
+    #[sql_type = "Text"]
+    pub name: String,
+    #[sql_type = "BigInt"]
+    pub member_count: i64,
+}
+
+#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq)]
+#[sql_type = "Text"]
+pub enum ShuffleMode {
+    Keep,
+    Shuffle,
+}
+
+text_enum!(ShuffleMode {
+    Keep => "keep",
+    Shuffle => "shuffle",
+});
+
+#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq)]
+#[sql_type = "Text"]
+pub enum Language {
+    En,
+    Uk,
+}
+
+text_enum!(Language {
+    En => "en",
+    Uk => "uk",
+});
@@ -1,8 +1,40 @@
+use chrono::{DateTime, Utc};
 use diesel::{prelude::*, PgConnection, QueryDsl};
+use std::collections::HashMap;
 
-use super::models::{self, Chat, Queue, QueueElement, QueueElementForQueue, QueueKey};
+use super::models::{
+    self, Chat, ChatSettings, ElementData, ElementRef, ElementStatus, ExpiredQueue, Language,
+    NewPollVote, NewQueue, NewQueueElement, NewRosterMember, NewScheduledQueue, NewSheetSlot,
+    NewSignup, NewSwapRequest, Queue, QueueElement, QueueElementForQueue, QueueKey, QueueMessage,
+    QueueStats, QueueSummary, QueueTemplate, Roster, RosterSummary, ScheduledQueue, Signup,
+    SignupEntry, Slots, SwapRequest,
+};
 use super::schema;
 
+/// Distance between the sort keys of neighbouring elements in a new or rebalanced queue.
+/// Halving it leaves room for 32 inserts at the same place before a rebalance.
+const SORT_KEY_STEP: i64 = 1 << 32;
+
+/// Queues left without changes for longer than their chats allow,
+/// the same ones `expiry::expiry_action` picks out.
+const EXPIRED_QUEUES: &str = "select q.id as queue_id \
+     from queues q join chat_settings s on s.chat_id = q.chat_id \
+     where s.expiry_days is not null \
+     and q.updated_at < now() - make_interval(days => s.expiry_days) \
+     and (s.expiry_action = 'delete' or not q.archived)";
+
+/// A sort key between two neighbours, either of which can be missing at the ends of the queue.
+/// Returns `None` if the keys are too close to each other.
+fn key_between(prev: Option<i64>, next: Option<i64>) -> Option<i64> {
+    match (prev, next) {
+        (None, None) => Some(SORT_KEY_STEP),
+        (Some(prev), None) => Some(prev + SORT_KEY_STEP),
+        (None, Some(next)) => Some(next - SORT_KEY_STEP),
+        (Some(prev), Some(next)) if next - prev > 1 => Some(prev + (next - prev) / 2),
+        (Some(_), Some(_)) => None,
+    }
+}
+
 pub struct QueueRepository {
     conn: PgConnection,
 }
@@ -17,7 +49,7 @@ impl QueueRepository {
         Ok(chats.filter(id.eq(chat_id)).first::<Chat>(&self.conn)?)
     }
 
-    pub fn create_new_queue(&self, queue: Queue) -> super::error::Result<models::Queue> {
+    pub fn create_new_queue(&self, queue: NewQueue) -> super::error::Result<models::Queue> {
         use schema::queues::dsl::queues;
 
         Ok(diesel::insert_into(queues)
@@ -25,21 +57,220 @@ impl QueueRepository {
             .get_result(&self.conn)?)
     }
 
+    /// Creates a sheet with a free slot for every label.
+    pub fn create_sheet(
+        &self,
+        chat: i64,
+        name: Option<String>,
+        labels: Vec<Option<String>>,
+    ) -> super::error::Result<(Queue, Vec<QueueElementForQueue>)> {
+        use super::error::Error;
+        use schema::{queue_elements as qe, queues as q};
+
+        self.conn.transaction::<_, Error, _>(|| {
+            let queue = diesel::insert_into(q::table)
+                .values((q::chat_id.eq(chat), q::qname.eq(name), q::sheet.eq(true)))
+                .get_result::<Queue>(&self.conn)?;
+
+            let mut slots = diesel::insert_into(qe::table)
+                .values(
+                    labels
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, label)| NewSheetSlot {
+                            element_name: String::new(),
+                            queue_id: queue.id,
+                            sort_key: (i as i64 + 1) * SORT_KEY_STEP,
+                            label,
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .get_results::<QueueElement>(&self.conn)?;
+            slots.sort_by_key(|x| x.sort_key);
+
+            Ok((queue, with_places(slots)))
+        })
+    }
+
+    /// Gives a free slot of a sheet to the user, who can't hold another one.
+    pub fn claim_slot(
+        &self,
+        queue: &QueueKey,
+        slot: ElementRef,
+        user: i64,
+        name: String,
+    ) -> super::error::Result<QueueElement> {
+        use super::error::Error;
+        use diesel::dsl::exists;
+        use schema::queue_elements as qe;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            let slot = self.find_element(queue, slot)?;
+            if slot.user_id.is_some() || !slot.element_name.is_empty() {
+                return Err(Error::SlotTaken);
+            }
+            let has_slot = diesel::select(exists(
+                qe::table
+                    .filter(qe::queue_id.eq(queue.id))
+                    .filter(qe::user_id.eq(user)),
+            ))
+            .get_result::<bool>(&self.conn)?;
+            if has_slot {
+                return Err(Error::AlreadyClaimed);
+            }
+
+            let claimed = diesel::update(qe::table.find(slot.id))
+                .set((qe::element_name.eq(name), qe::user_id.eq(user)))
+                .get_result(&self.conn)?;
+            self.bump_queue_version(queue)?;
+            Ok(claimed)
+        })
+    }
+
+    /// Frees the slot of the user, returns `None` if they had none.
+    pub fn release_slot(
+        &self,
+        queue: &QueueKey,
+        user: i64,
+    ) -> super::error::Result<Option<QueueElement>> {
+        use super::error::Error;
+        use schema::queue_elements as qe;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            let released = diesel::update(
+                qe::table
+                    .filter(qe::queue_id.eq(queue.id))
+                    .filter(qe::user_id.eq(user)),
+            )
+            .set((
+                qe::element_name.eq(""),
+                qe::user_id.eq(None::<i64>),
+                qe::status.eq(ElementStatus::Waiting),
+            ))
+            .get_result::<QueueElement>(&self.conn)
+            .optional()?;
+
+            if released.is_some() {
+                self.bump_queue_version(queue)?;
+            }
+            Ok(released)
+        })
+    }
+
+    pub fn add_queue_message(
+        &self,
+        message: QueueMessage,
+    ) -> super::error::Result<models::QueueMessage> {
+        use schema::queue_messages::dsl::queue_messages;
+
+        Ok(diesel::insert_into(queue_messages)
+            .values(message)
+            .get_result(&self.conn)?)
+    }
+
+    /// Records that the message was edited to show the version.
+    pub fn set_message_version(
+        &self,
+        message: &QueueMessage,
+        new_version: i32,
+    ) -> super::error::Result<()> {
+        use schema::queue_messages::dsl::*;
+
+        diesel::update(
+            queue_messages
+                .find((message.message_id, message.chat_id))
+                .filter(version.ne(new_version)),
+        )
+        .set(version.eq(new_version))
+        .execute(&self.conn)?;
+        Ok(())
+    }
+
+    pub fn get_queue_messages(&self, queue: &QueueKey) -> super::error::Result<Vec<QueueMessage>> {
+        use schema::queue_messages::dsl::*;
+
+        Ok(queue_messages
+            .filter(queue_id.eq(queue.id))
+            .order(message_id)
+            .load::<QueueMessage>(&self.conn)?)
+    }
+
+    pub fn get_queue_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+    ) -> super::error::Result<Option<QueueMessage>> {
+        use schema::queue_messages::dsl;
+
+        Ok(dsl::queue_messages
+            .find((message_id, chat_id))
+            .first::<QueueMessage>(&self.conn)
+            .optional()?)
+    }
+
+    pub fn set_message_page(
+        &self,
+        message: &QueueMessage,
+        new_page: i32,
+    ) -> super::error::Result<QueueMessage> {
+        use schema::queue_messages::dsl::*;
+
+        Ok(
+            diesel::update(queue_messages.find((message.message_id, message.chat_id)))
+                .set(page.eq(new_page))
+                .get_result(&self.conn)?,
+        )
+    }
+
+    pub fn remove_queue_message(&self, message: &QueueMessage) -> super::error::Result<()> {
+        use schema::queue_messages::dsl::*;
+
+        diesel::delete(
+            queue_messages.filter(
+                message_id
+                    .eq(message.message_id)
+                    .and(chat_id.eq(message.chat_id)),
+            ),
+        )
+        .execute(&self.conn)?;
+        Ok(())
+    }
+
+    /// Fills the queue with elements placed in the given order.
     pub fn insert_filled_queue(
         &self,
         queue: QueueKey,
-        queue_elems: Vec<QueueElementForQueue>,
-    ) -> super::error::Result<Vec<QueueElement>> {
+        elements: Vec<ElementData>,
+    ) -> super::error::Result<Vec<QueueElementForQueue>> {
+        self.insert_labelled_queue(queue, elements.into_iter().map(|x| (x, None)).collect())
+    }
+
+    /// Fills the queue with elements placed in the given order, each with its label.
+    pub fn insert_labelled_queue(
+        &self,
+        queue: QueueKey,
+        elements: Vec<(ElementData, Option<String>)>,
+    ) -> super::error::Result<Vec<QueueElementForQueue>> {
         use schema::queue_elements::dsl::*;
 
-        Ok(diesel::insert_into(queue_elements)
+        let mut inserted = diesel::insert_into(queue_elements)
             .values(
-                queue_elems
+                elements
                     .into_iter()
-                    .map(|x| QueueElement::from_parts(queue.clone(), x))
-                    .collect::<Vec<QueueElement>>(),
+                    .enumerate()
+                    .map(|(i, (element, element_label))| NewQueueElement {
+                        element_name: element.element_name,
+                        queue_id: queue.id,
+                        sort_key: (i as i64 + 1) * SORT_KEY_STEP,
+                        user_id: element.user_id,
+                        label: element_label,
+                    })
+                    .collect::<Vec<NewQueueElement>>(),
             )
-            .get_results(&self.conn)?)
+            .get_results::<QueueElement>(&self.conn)?;
+        inserted.sort_by_key(|x| x.sort_key);
+
+        Ok(with_places(inserted))
     }
 
     pub fn get_or_create_chat(&self, chat_id: i64) -> super::error::Result<models::Chat> {
@@ -52,35 +283,100 @@ impl QueueRepository {
         })
     }
 
+    /// The languages chosen in the chats, chats which didn't choose one are left out.
+    /// The private chat of a user has the id of the user.
+    pub fn chosen_languages(&self, chats: &[i64]) -> super::error::Result<HashMap<i64, Language>> {
+        use schema::chat_settings::dsl::*;
+
+        Ok(chat_settings
+            .filter(chat_id.eq_any(chats))
+            .select((chat_id, language))
+            .load::<(i64, Option<Language>)>(&self.conn)?
+            .into_iter()
+            .filter_map(|(chat, chosen)| Some((chat, chosen?)))
+            .collect())
+    }
+
+    /// The template of the chat, chats which never set one get the defaults.
+    pub fn get_template(&self, chat: i64) -> super::error::Result<QueueTemplate> {
+        use schema::queue_templates::dsl::*;
+
+        diesel::insert_into(queue_templates)
+            .values(chat_id.eq(chat))
+            .on_conflict_do_nothing()
+            .execute(&self.conn)?;
+        Ok(queue_templates.find(chat).first(&self.conn)?)
+    }
+
+    pub fn update_template(&self, template: &QueueTemplate) -> super::error::Result<QueueTemplate> {
+        use schema::queue_templates::dsl::*;
+
+        Ok(diesel::update(queue_templates.find(template.chat_id))
+            .set(template)
+            .get_result(&self.conn)?)
+    }
+
+    pub fn reset_template(&self, chat: i64) -> super::error::Result<QueueTemplate> {
+        use schema::queue_templates::dsl::*;
+
+        diesel::delete(queue_templates.find(chat)).execute(&self.conn)?;
+        self.get_template(chat)
+    }
+
+    /// The settings of the chat, chats which never changed them get the defaults.
+    pub fn get_settings(&self, chat: i64) -> super::error::Result<ChatSettings> {
+        use schema::chat_settings::dsl::*;
+
+        diesel::insert_into(chat_settings)
+            .values(chat_id.eq(chat))
+            .on_conflict_do_nothing()
+            .execute(&self.conn)?;
+        Ok(chat_settings.find(chat).first(&self.conn)?)
+    }
+
+    pub fn update_settings(&self, settings: &ChatSettings) -> super::error::Result<ChatSettings> {
+        use schema::chat_settings::dsl::*;
+
+        Ok(diesel::update(chat_settings.find(settings.chat_id))
+            .set(settings)
+            .get_result(&self.conn)?)
+    }
+
     pub fn get_elements_for_queue(
         &self,
         queue: &QueueKey,
     ) -> super::error::Result<Vec<QueueElementForQueue>> {
-        use queue_elements as qe;
-        use schema::*;
-
-        Ok(queues::table
-            .inner_join(
-                queue_elements::table.on(queues::id
-                    .eq(queue_elements::queue_id)
-                    .and(queues::chat_id.eq(queue_elements::chat_id))),
-            )
-            .filter(
-                queues::id
-                    .eq(&queue.id)
-                    .and(queues::chat_id.eq(&queue.chat_id)),
-            )
-            .order(qe::queue_place)
-            .select((qe::element_name, qe::queue_place))
-            .load::<QueueElementForQueue>(&self.conn)?)
+        use schema::queue_elements as qe;
+
+        let elems = qe::table
+            .filter(qe::queue_id.eq(&queue.id))
+            .order(qe::sort_key)
+            .load::<QueueElement>(&self.conn)?;
+        Ok(with_places(elems))
     }
 
-    pub fn queue_exists(&self, queue: QueueKey) -> super::error::Result<Option<Queue>> {
-        use schema::queues::dsl::*;
+    /// The people of the queue in its order, the free slots of a sheet are nobody.
+    pub fn get_people_of_queue(&self, queue: &Queue) -> super::error::Result<Vec<ElementData>> {
+        Ok(self
+            .get_elements_for_queue(&queue.key())?
+            .into_iter()
+            .filter(|x| !x.is_free_slot(queue))
+            .map(ElementData::from)
+            .collect())
+    }
+
+    pub fn queue_for_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+    ) -> super::error::Result<Option<Queue>> {
+        use schema::{queue_messages as qm, queues as q};
 
         Ok(
-            match queues
-                .filter(chat_id.eq(queue.chat_id).and(id.eq(queue.id)))
+            match q::table
+                .inner_join(qm::table)
+                .filter(qm::chat_id.eq(chat_id).and(qm::message_id.eq(message_id)))
+                .select(q::all_columns)
                 .first::<Queue>(&self.conn)
             {
                 Ok(queue) => Some(queue),
@@ -90,144 +386,956 @@ impl QueueRepository {
         )
     }
 
-    pub fn swap_positions_for_queue(
+    /// Counts the queues of the chat which aren't archived.
+    pub fn count_queues(&self, chat: i64) -> super::error::Result<i64> {
+        use schema::queues::dsl::*;
+
+        Ok(queues
+            .filter(chat_id.eq(chat).and(archived.eq(false)))
+            .count()
+            .get_result(&self.conn)?)
+    }
+
+    /// Queues of the chat which aren't archived, the newest first.
+    pub fn list_queues(
         &self,
-        queue: &QueueKey,
-        pos1: i32,
-        pos2: i32,
-    ) -> Result<(), super::error::Error> {
+        chat: i64,
+        offset: i64,
+        limit: i64,
+    ) -> super::error::Result<Vec<QueueSummary>> {
+        use diesel::sql_types::{Array, BigInt};
+        use schema::queues as q;
+
+        let listed = q::table
+            .filter(q::chat_id.eq(chat).and(q::archived.eq(false)))
+            .order((q::created_at.desc(), q::id.desc()))
+            .offset(offset)
+            .limit(limit)
+            .load::<Queue>(&self.conn)?;
+
+        let stats = diesel::sql_query(
+            "select q.id as queue_id, \
+             (select count(*) from queue_elements e where e.queue_id = q.id) as element_count, \
+             (select max(message_id) from queue_messages m where m.queue_id = q.id) as message_id \
+             from queues q where q.id = any($1)",
+        )
+        .bind::<Array<BigInt>, _>(listed.iter().map(|x| x.id).collect::<Vec<_>>())
+        .load::<QueueStats>(&self.conn)?;
+
+        Ok(listed
+            .into_iter()
+            .map(|queue| {
+                let stats = stats.iter().find(|x| x.queue_id == queue.id);
+                QueueSummary {
+                    element_count: stats.map_or(0, |x| x.element_count),
+                    message_id: stats.and_then(|x| x.message_id),
+                    queue,
+                }
+            })
+            .collect())
+    }
+
+    pub fn archive_queue(&self, queue: &QueueKey) -> super::error::Result<Queue> {
+        use schema::queues::dsl::*;
+
+        Ok(diesel::update(queues.find(queue.id))
+            .set(archived.eq(true))
+            .get_result(&self.conn)?)
+    }
+
+    pub fn expired_queues(&self) -> super::error::Result<Vec<ExpiredQueue>> {
+        Ok(diesel::sql_query(EXPIRED_QUEUES).load::<ExpiredQueue>(&self.conn)?)
+    }
+
+    /// Like `get_queue`, `None` if the queue was deleted.
+    pub fn find_queue(&self, queue: &QueueKey) -> super::error::Result<Option<Queue>> {
+        use schema::queues::dsl::*;
+
+        Ok(queues
+            .find(queue.id)
+            .first::<Queue>(&self.conn)
+            .optional()?)
+    }
+
+    /// Deletes the queue together with its elements and messages.
+    pub fn delete_queue(&self, queue: &QueueKey) -> super::error::Result<Queue> {
+        use schema::queues::dsl::*;
+
+        Ok(diesel::delete(queues.find(queue.id)).get_result(&self.conn)?)
+    }
+
+    pub fn create_schedule(
+        &self,
+        schedule: NewScheduledQueue,
+    ) -> super::error::Result<ScheduledQueue> {
+        use schema::scheduled_queues::dsl::*;
+
+        Ok(diesel::insert_into(scheduled_queues)
+            .values(schedule)
+            .get_result(&self.conn)?)
+    }
+
+    pub fn list_schedules(&self, chat: i64) -> super::error::Result<Vec<ScheduledQueue>> {
+        use schema::scheduled_queues::dsl::*;
+
+        Ok(scheduled_queues
+            .filter(chat_id.eq(chat))
+            .order(id)
+            .load(&self.conn)?)
+    }
+
+    /// Returns whether the chat had the schedule.
+    pub fn delete_schedule(&self, chat: i64, schedule: i64) -> super::error::Result<bool> {
+        use schema::scheduled_queues::dsl::*;
+
+        let deleted = diesel::delete(scheduled_queues.find(schedule).filter(chat_id.eq(chat)))
+            .execute(&self.conn)?;
+        Ok(deleted > 0)
+    }
+
+    /// Schedules whose queues should have been created by now.
+    pub fn due_schedules(&self) -> super::error::Result<Vec<ScheduledQueue>> {
+        use diesel::dsl::now;
+        use schema::scheduled_queues::dsl::*;
+
+        Ok(scheduled_queues
+            .filter(next_run_at.le(now))
+            .order(next_run_at)
+            .load(&self.conn)?)
+    }
+
+    /// Moves the schedule to its next run and number. Returns `None` if the schedule
+    /// was already moved or removed, so every run creates one queue at most.
+    pub fn advance_schedule(
+        &self,
+        schedule: &ScheduledQueue,
+        next_run: DateTime<Utc>,
+    ) -> super::error::Result<Option<ScheduledQueue>> {
+        use schema::scheduled_queues::dsl::*;
+
+        Ok(diesel::update(
+            scheduled_queues
+                .find(schedule.id)
+                .filter(next_run_at.eq(schedule.next_run_at)),
+        )
+        .set((next_run_at.eq(next_run), next_number.eq(next_number + 1)))
+        .get_result(&self.conn)
+        .optional()?)
+    }
+
+    /// Creates the chat's roster with the name, or replaces the members of the existing one.
+    pub fn save_roster(
+        &self,
+        chat: i64,
+        roster_name: &str,
+        members: Vec<ElementData>,
+    ) -> super::error::Result<Roster> {
         use super::error::Error;
-        use schema::queue_elements::dsl::*;
+        use schema::roster_members::dsl as rm;
+        use schema::rosters::dsl as r;
 
-        let pos_filter = |pos| {
-            queue_elements.filter(
-                queue_id
-                    .eq(&queue.id)
-                    .and(chat_id.eq(&queue.chat_id).and(queue_place.eq(pos))),
-            )
-        };
+        self.conn.transaction::<_, Error, _>(|| {
+            let roster: Roster = diesel::insert_into(r::rosters)
+                .values((r::chat_id.eq(chat), r::name.eq(roster_name)))
+                .on_conflict((r::chat_id, r::name))
+                .do_update()
+                .set(r::updated_at.eq(diesel::dsl::now))
+                .get_result(&self.conn)?;
+
+            diesel::delete(rm::roster_members.filter(rm::roster_id.eq(roster.id)))
+                .execute(&self.conn)?;
+            self.insert_roster_members(&roster, members)?;
+            Ok(roster)
+        })
+    }
 
-        let f_query = |pos| -> Result<_, Error> {
-            match pos_filter(pos).first::<QueueElement>(&self.conn) {
-                Ok(exists) => Ok(exists),
-                Err(diesel::result::Error::NotFound) => {
-                    Err(Error::NonexistentPosition { pos })
+    /// Saves the members as a new roster, numbering the name if the chat already has it.
+    pub fn save_new_roster(
+        &self,
+        chat: i64,
+        roster_name: &str,
+        members: Vec<ElementData>,
+    ) -> super::error::Result<Roster> {
+        use super::error::Error;
+        use schema::rosters::dsl as r;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            let mut number = 1;
+            let roster = loop {
+                let candidate = match number {
+                    1 => roster_name.to_string(),
+                    _ => format!("{} ({})", roster_name, number),
+                };
+                let roster: Option<Roster> = diesel::insert_into(r::rosters)
+                    .values((r::chat_id.eq(chat), r::name.eq(candidate)))
+                    .on_conflict_do_nothing()
+                    .get_result(&self.conn)
+                    .optional()?;
+                match roster {
+                    Some(roster) => break roster,
+                    None => number += 1,
                 }
-                Err(e) => Err(e.into()),
+            };
+            self.insert_roster_members(&roster, members)?;
+            Ok(roster)
+        })
+    }
+
+    fn insert_roster_members(
+        &self,
+        roster: &Roster,
+        members: Vec<ElementData>,
+    ) -> super::error::Result<()> {
+        use schema::roster_members::dsl::*;
+
+        diesel::insert_into(roster_members)
+            .values(
+                members
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, member)| NewRosterMember {
+                        roster_id: roster.id,
+                        position: i as i32 + 1,
+                        element_name: member.element_name,
+                        user_id: member.user_id,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    pub fn find_roster(
+        &self,
+        chat: i64,
+        roster_name: &str,
+    ) -> super::error::Result<Option<Roster>> {
+        use schema::rosters::dsl::*;
+
+        Ok(rosters
+            .filter(chat_id.eq(chat))
+            .filter(name.eq(roster_name))
+            .first(&self.conn)
+            .optional()?)
+    }
+
+    pub fn get_roster_members(&self, roster: i64) -> super::error::Result<Vec<ElementData>> {
+        use schema::roster_members::dsl::*;
+
+        Ok(roster_members
+            .filter(roster_id.eq(roster))
+            .order(position)
+            .select((element_name, user_id))
+            .load::<(String, Option<i64>)>(&self.conn)?
+            .into_iter()
+            .map(|(member_name, member_user_id)| ElementData {
+                element_name: member_name,
+                user_id: member_user_id,
+            })
+            .collect())
+    }
+
+    pub fn list_rosters(&self, chat: i64) -> super::error::Result<Vec<RosterSummary>> {
+        use diesel::sql_types::BigInt;
+
+        Ok(diesel::sql_query(
+            "select r.name, \
+             (select count(*) from roster_members m where m.roster_id = r.id) as member_count \
+             from rosters r where r.chat_id = $1 order by r.name",
+        )
+        .bind::<BigInt, _>(chat)
+        .load(&self.conn)?)
+    }
+
+    /// Returns whether the chat had the roster.
+    pub fn delete_roster(&self, chat: i64, roster_name: &str) -> super::error::Result<bool> {
+        use schema::rosters::dsl::*;
+
+        let deleted = diesel::delete(
+            rosters
+                .filter(chat_id.eq(chat))
+                .filter(name.eq(roster_name)),
+        )
+        .execute(&self.conn)?;
+        Ok(deleted > 0)
+    }
+
+    /// How many times each two people of the chat were paired, keyed by `(first, second)`.
+    pub fn get_pair_history(&self, chat: i64) -> super::error::Result<Vec<(String, String, i32)>> {
+        use schema::pair_history::dsl::*;
+
+        Ok(pair_history
+            .filter(chat_id.eq(chat))
+            .select((first_key, second_key, times))
+            .load(&self.conn)?)
+    }
+
+    /// Counts one more meeting of every pair, the keys of each have to be in order.
+    pub fn record_pairs(&self, chat: i64, pairs: &[(String, String)]) -> super::error::Result<()> {
+        use super::error::Error;
+        use schema::pair_history::dsl::*;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            for (first, second) in pairs {
+                diesel::insert_into(pair_history)
+                    .values((chat_id.eq(chat), first_key.eq(first), second_key.eq(second)))
+                    .on_conflict((chat_id, first_key, second_key))
+                    .do_update()
+                    .set(times.eq(times + 1))
+                    .execute(&self.conn)?;
             }
-        };
-        let pos1 = f_query(pos1)?;
-        let pos2 = f_query(pos2)?;
+            Ok(())
+        })
+    }
+
+    /// Returns whether the chat had any pairs.
+    pub fn clear_pair_history(&self, chat: i64) -> super::error::Result<bool> {
+        use schema::pair_history::dsl::*;
+
+        let deleted = diesel::delete(pair_history.filter(chat_id.eq(chat))).execute(&self.conn)?;
+        Ok(deleted > 0)
+    }
+
+    pub fn create_signup(&self, signup: NewSignup) -> super::error::Result<Signup> {
+        use schema::signups::dsl::*;
+
+        Ok(diesel::insert_into(signups)
+            .values(signup)
+            .get_result(&self.conn)?)
+    }
+
+    pub fn set_signup_message(&self, signup: i64, message: i64) -> super::error::Result<()> {
+        use schema::signups::dsl::*;
+
+        diesel::update(signups.find(signup))
+            .set(message_id.eq(message))
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    pub fn find_signup(&self, signup: i64) -> super::error::Result<Option<Signup>> {
+        use schema::signups::dsl::*;
+
+        Ok(signups.find(signup).first(&self.conn).optional()?)
+    }
+
+    /// Signs the user up, returns `false` if they already were.
+    /// Fails with `SignupClosed` once the deadline has passed.
+    pub fn add_signup_entry(
+        &self,
+        signup: i64,
+        user: i64,
+        name: String,
+    ) -> super::error::Result<bool> {
+        use super::error::Error;
+        use schema::signup_entries::dsl::*;
 
         self.conn.transaction::<_, Error, _>(|| {
-            diesel::update(pos_filter(pos1.queue_place))
-                .set(queue_place.eq(-1))
+            self.lock_open_signup(signup)?;
+            let inserted = diesel::insert_into(signup_entries)
+                .values((
+                    signup_id.eq(signup),
+                    user_id.eq(user),
+                    element_name.eq(name),
+                ))
+                .on_conflict_do_nothing()
                 .execute(&self.conn)?;
+            Ok(inserted > 0)
+        })
+    }
+
+    /// Takes the user off the sign-up, returns `false` if they weren't on it.
+    pub fn remove_signup_entry(&self, signup: i64, user: i64) -> super::error::Result<bool> {
+        use super::error::Error;
+        use schema::signup_entries::dsl::*;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            self.lock_open_signup(signup)?;
+            let deleted =
+                diesel::delete(signup_entries.find((signup, user))).execute(&self.conn)?;
+            Ok(deleted > 0)
+        })
+    }
+
+    /// Keeps the sign-up from being closed until the transaction ends.
+    fn lock_open_signup(&self, signup: i64) -> super::error::Result<()> {
+        use super::error::Error;
+        use diesel::dsl::now;
+        use schema::signups::dsl::*;
+
+        signups
+            .find(signup)
+            .filter(closed.eq(false))
+            .filter(closes_at.gt(now))
+            .select(id)
+            .for_update()
+            .first::<i64>(&self.conn)
+            .optional()?
+            .ok_or(Error::SignupClosed)?;
+        Ok(())
+    }
+
+    /// Entries in the order the users signed up.
+    pub fn get_signup_entries(&self, signup: i64) -> super::error::Result<Vec<SignupEntry>> {
+        use schema::signup_entries::dsl::*;
+
+        Ok(signup_entries
+            .filter(signup_id.eq(signup))
+            .order((signed_up_at, user_id))
+            .load(&self.conn)?)
+    }
+
+    /// Open sign-ups whose deadline has passed.
+    pub fn due_signups(&self) -> super::error::Result<Vec<Signup>> {
+        use diesel::dsl::now;
+        use schema::signups::dsl::*;
 
-            diesel::update(pos_filter(pos2.queue_place))
-                .set(queue_place.eq(pos1.queue_place))
+        Ok(signups
+            .filter(closed.eq(false))
+            .filter(closes_at.le(now))
+            .load(&self.conn)?)
+    }
+
+    /// Returns `None` if the sign-up was already closed, so it is closed once.
+    pub fn close_signup(&self, signup: i64) -> super::error::Result<Option<Signup>> {
+        use schema::signups::dsl::*;
+
+        Ok(
+            diesel::update(signups.find(signup).filter(closed.eq(false)))
+                .set(closed.eq(true))
+                .get_result(&self.conn)
+                .optional()?,
+        )
+    }
+
+    pub fn reopen_signup(&self, signup: i64) -> super::error::Result<()> {
+        use schema::signups::dsl::*;
+
+        diesel::update(signups.find(signup))
+            .set(closed.eq(false))
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    pub fn delete_signup(&self, signup: i64) -> super::error::Result<()> {
+        use schema::signups::dsl::*;
+
+        diesel::delete(signups.find(signup)).execute(&self.conn)?;
+        Ok(())
+    }
+
+    /// Keeps the latest answer of the user, a retracted vote is forgotten.
+    pub fn save_poll_vote(&self, vote: NewPollVote) -> super::error::Result<()> {
+        use diesel::dsl::now;
+        use schema::poll_votes::dsl::*;
+
+        if vote.option_ids.is_empty() {
+            diesel::delete(poll_votes.find((vote.poll_id, vote.user_id))).execute(&self.conn)?;
+            return Ok(());
+        }
+
+        diesel::insert_into(poll_votes)
+            .values(&vote)
+            .on_conflict((poll_id, user_id))
+            .do_update()
+            .set((
+                element_name.eq(&vote.element_name),
+                option_ids.eq(&vote.option_ids),
+                voted_at.eq(now),
+            ))
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    /// Users who voted for the option, in the order they voted.
+    pub fn poll_voters(&self, poll: &str, option: i32) -> super::error::Result<Vec<ElementData>> {
+        use schema::poll_votes::dsl::*;
+
+        Ok(poll_votes
+            .filter(poll_id.eq(poll))
+            .filter(option_ids.contains(vec![option]))
+            .order((voted_at, user_id))
+            .select((element_name, user_id))
+            .load::<(String, i64)>(&self.conn)?
+            .into_iter()
+            .map(|(voter_name, voter_id)| ElementData {
+                element_name: voter_name,
+                user_id: Some(voter_id),
+            })
+            .collect())
+    }
+
+    pub fn create_swap_request(
+        &self,
+        request: NewSwapRequest,
+    ) -> super::error::Result<SwapRequest> {
+        use schema::swap_requests::dsl::*;
+
+        Ok(diesel::insert_into(swap_requests)
+            .values(request)
+            .get_result(&self.conn)?)
+    }
+
+    pub fn set_swap_request_message(&self, request: i64, message: i64) -> super::error::Result<()> {
+        use schema::swap_requests::dsl::*;
+
+        diesel::update(swap_requests.find(request))
+            .set(message_id.eq(message))
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    /// Finds a request which hasn't expired yet.
+    pub fn find_swap_request(&self, request: i64) -> super::error::Result<Option<SwapRequest>> {
+        use diesel::dsl::now;
+        use schema::swap_requests::dsl::*;
+
+        Ok(swap_requests
+            .find(request)
+            .filter(expires_at.gt(now))
+            .first(&self.conn)
+            .optional()?)
+    }
+
+    /// Deletes a request which hasn't expired yet, so it is answered once.
+    pub fn take_swap_request(&self, request: i64) -> super::error::Result<Option<SwapRequest>> {
+        use diesel::dsl::now;
+        use schema::swap_requests::dsl::*;
+
+        Ok(
+            diesel::delete(swap_requests.find(request).filter(expires_at.gt(now)))
+                .get_result(&self.conn)
+                .optional()?,
+        )
+    }
+
+    /// Deletes expired requests and returns them.
+    pub fn take_expired_swap_requests(&self) -> super::error::Result<Vec<SwapRequest>> {
+        use diesel::dsl::now;
+        use schema::swap_requests::dsl::*;
+
+        Ok(diesel::delete(swap_requests.filter(expires_at.le(now))).get_results(&self.conn)?)
+    }
+
+    /// The newest queue of the chat with the name which isn't archived.
+    pub fn find_queue_by_name(&self, chat: i64, name: &str) -> super::error::Result<Option<Queue>> {
+        use schema::queues::dsl::*;
+
+        Ok(queues
+            .filter(chat_id.eq(chat))
+            .filter(qname.eq(name))
+            .filter(archived.eq(false))
+            .order((created_at.desc(), id.desc()))
+            .first(&self.conn)
+            .optional()?)
+    }
+
+    pub fn get_queue(&self, queue: &QueueKey) -> super::error::Result<Queue> {
+        use schema::queues::dsl::*;
+
+        Ok(queues.find(queue.id).first::<Queue>(&self.conn)?)
+    }
+
+    /// Waits for other connections to finish with the queue and locks it
+    /// until `unlock_queue` or until this connection is closed. Blocks the thread.
+    pub fn lock_queue(&self, queue: &QueueKey) -> super::error::Result<()> {
+        use diesel::sql_types::BigInt;
+
+        diesel::sql_query("select pg_advisory_lock($1)")
+            .bind::<BigInt, _>(queue.id)
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    pub fn unlock_queue(&self, queue: &QueueKey) -> super::error::Result<()> {
+        use diesel::sql_types::BigInt;
+
+        diesel::sql_query("select pg_advisory_unlock($1)")
+            .bind::<BigInt, _>(queue.id)
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    pub fn find_element(
+        &self,
+        queue: &QueueKey,
+        elem: ElementRef,
+    ) -> super::error::Result<QueueElement> {
+        use super::error::Error;
+        use schema::queue_elements as qe;
+
+        let query = qe::table.filter(qe::queue_id.eq(&queue.id)).into_boxed();
+        let query = match elem {
+            ElementRef::Place(pos) if pos < 1 => {
+                return Err(Error::NonexistentPosition { pos });
+            }
+            ElementRef::Place(pos) => query.order(qe::sort_key).offset(pos as i64 - 1),
+            ElementRef::Id(elem_id) => query.filter(qe::id.eq(elem_id)),
+        };
+
+        match query.first::<QueueElement>(&self.conn) {
+            Ok(exists) => Ok(exists),
+            Err(diesel::result::Error::NotFound) => Err(match elem {
+                ElementRef::Place(pos) => Error::NonexistentPosition { pos },
+                ElementRef::Id(id) => Error::NonexistentElement { id },
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Swaps two elements and returns them as they were before the swap.
+    pub fn swap_positions_for_queue(
+        &self,
+        queue: &QueueKey,
+        elem1: ElementRef,
+        elem2: ElementRef,
+    ) -> super::error::Result<(QueueElement, QueueElement)> {
+        use super::error::Error;
+        use schema::queue_elements::dsl::*;
+
+        let elem1 = self.find_element(queue, elem1)?;
+        let elem2 = self.find_element(queue, elem2)?;
+
+        // the sort key constraint is deferred, so the keys can be exchanged directly
+        self.conn.transaction::<_, Error, _>(|| {
+            diesel::update(queue_elements.find(elem1.id))
+                .set(sort_key.eq(elem2.sort_key))
                 .execute(&self.conn)?;
 
-            diesel::update(pos_filter(-1))
-                .set(queue_place.eq(pos2.queue_place))
+            diesel::update(queue_elements.find(elem2.id))
+                .set(sort_key.eq(elem1.sort_key))
                 .execute(&self.conn)?;
 
-            Ok(())
+            self.bump_queue_version(queue)
         })?;
 
-        Ok(())
+        Ok((elem1, elem2))
     }
 
+    /// Inserts an element before the one at `index`, or to the end of the queue.
     pub fn insert_new_elem(
         &self,
         queue: &QueueKey,
-        name: String,
+        element: ElementData,
         index: Option<i32>,
-    ) -> super::error::Result<()> {
+    ) -> super::error::Result<QueueElement> {
         use super::error::Error;
-        use diesel::dsl::*;
         use schema::queue_elements as qe;
 
-        let index: i32 = index
-            .map(|x| Ok(Some(x)))
-            .unwrap_or_else(|| -> Result<Option<i32>, diesel::result::Error> {
-                qe::table
-                    .filter(
-                        qe::queue_id
-                            .eq(queue.id)
-                            .and(qe::chat_id.eq(&queue.chat_id)),
-                    )
-                    .select(max(qe::queue_place) + 1)
-                    .first(&self.conn)
-            })?
-            .ok_or_else(||Error::Wtf(
-                "Tried to unwrap but there was no value? How?".to_string(),
-            ))?;
-
         self.conn.transaction(|| -> Result<_, Error> {
-            diesel::update(
-                qe::table.filter(
-                    qe::queue_id
-                        .eq(queue.id)
-                        .and(qe::chat_id.eq(&queue.chat_id))
-                        .and(qe::queue_place.ge(index)),
-                ),
-            )
-            .set(qe::queue_place.eq(qe::queue_place + 1))
-            .execute(&self.conn)?;
+            let key = match self.free_sort_key(queue, index)? {
+                Some(key) => key,
+                None => {
+                    self.rebalance_queue(queue)?;
+                    self.free_sort_key(queue, index)?.ok_or_else(|| {
+                        Error::Wtf("No free sort key right after a rebalance.".to_string())
+                    })?
+                }
+            };
 
-            diesel::insert_into(qe::table)
-                .values(QueueElement {
-                    element_name: name,
+            let elem = diesel::insert_into(qe::table)
+                .values(NewQueueElement {
+                    element_name: element.element_name,
                     queue_id: queue.id,
-                    chat_id: queue.chat_id,
-                    queue_place: index,
+                    sort_key: key,
+                    user_id: element.user_id,
+                    label: None,
                 })
-                .execute(&self.conn)?;
-
-            Ok(())
-        })?;
+                .get_result(&self.conn)?;
 
-        Ok(())
+            self.bump_queue_version(queue)?;
+            Ok(elem)
+        })
     }
 
-    pub fn remove_elem(&self, queue: &QueueKey, index: i32) -> super::error::Result<String> {
+    /// Removes an element and returns it as it was before the removal.
+    pub fn remove_elem(
+        &self,
+        queue: &QueueKey,
+        elem: ElementRef,
+    ) -> super::error::Result<QueueElement> {
         use super::error::Error;
         use schema::queue_elements as qe;
 
-        let deleted_name = self.conn.transaction::<_, Error, _>(|| {
-            let elem_name = match diesel::delete(
-                qe::table.filter(
-                    qe::queue_id
-                        .eq(queue.id)
-                        .and(qe::chat_id.eq(&queue.chat_id))
-                        .and(qe::queue_place.eq(index)),
-                ),
-            )
-            .returning(qe::element_name)
-            .get_result::<String>(&self.conn)
-            {
-                Err(diesel::result::Error::NotFound) => {
-                    return Err(Error::NonexistentPosition { pos: index });
-                }
-                v => v,
-            }?;
+        self.conn.transaction::<_, Error, _>(|| {
+            let elem = self.find_element(queue, elem)?;
+
+            diesel::delete(qe::table.find(elem.id)).execute(&self.conn)?;
 
+            self.bump_queue_version(queue)?;
+            Ok(elem)
+        })
+    }
+
+    /// Marks the current element as done and makes the next waiting one current.
+    /// Returns the new current element, `None` if nobody is left.
+    pub fn advance_queue(&self, queue: &QueueKey) -> super::error::Result<Option<QueueElement>> {
+        use super::error::Error;
+        use schema::queue_elements as qe;
+
+        self.conn.transaction::<_, Error, _>(|| {
             diesel::update(
-                qe::table.filter(
-                    qe::queue_id
-                        .eq(queue.id)
-                        .and(qe::chat_id.eq(&queue.chat_id))
-                        .and(qe::queue_place.ge(index)),
-                ),
+                qe::table
+                    .filter(qe::queue_id.eq(queue.id))
+                    .filter(qe::status.eq(ElementStatus::Current)),
             )
-            .set(qe::queue_place.eq(qe::queue_place - 1))
+            .set(qe::status.eq(ElementStatus::Done))
             .execute(&self.conn)?;
 
-            Ok(elem_name)
-        })?;
+            let next = self.call_next(queue)?;
+            self.bump_queue_version(queue)?;
+            Ok(next)
+        })
+    }
+
+    /// Moves the current element back and calls the next one.
+    /// Returns the skipped element and the called one, `None` if nobody is current.
+    pub fn skip_current(
+        &self,
+        queue: &QueueKey,
+        places: Option<i32>,
+    ) -> super::error::Result<Option<(QueueElement, Option<QueueElement>)>> {
+        use super::error::Error;
+        use schema::queue_elements as qe;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            let current = qe::table
+                .filter(qe::queue_id.eq(queue.id))
+                .filter(qe::status.eq(ElementStatus::Current))
+                .first::<QueueElement>(&self.conn)
+                .optional()?;
+            let current = match current {
+                Some(current) => current,
+                None => return Ok(None),
+            };
+
+            self.move_elem_back(queue, current.id, places)?;
+            let skipped = diesel::update(qe::table.find(current.id))
+                .set(qe::status.eq(ElementStatus::Skipped))
+                .get_result::<QueueElement>(&self.conn)?;
+            let next = self.call_next(queue)?;
+
+            self.bump_queue_version(queue)?;
+            Ok(Some((skipped, next)))
+        })
+    }
+
+    /// Moves an element `places` back, or to the end for `None`.
+    pub fn move_back(
+        &self,
+        queue: &QueueKey,
+        elem_id: i64,
+        places: Option<i32>,
+    ) -> super::error::Result<QueueElement> {
+        use super::error::Error;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            let moved = self.move_elem_back(queue, elem_id, places)?;
+            self.bump_queue_version(queue)?;
+            Ok(moved)
+        })
+    }
+
+    /// The first element of the user which isn't done.
+    pub fn find_user_element(
+        &self,
+        queue: &QueueKey,
+        user: i64,
+    ) -> super::error::Result<Option<QueueElement>> {
+        use schema::queue_elements as qe;
+
+        Ok(qe::table
+            .filter(qe::queue_id.eq(queue.id))
+            .filter(qe::user_id.eq(user))
+            .filter(qe::status.ne(ElementStatus::Done))
+            .order(qe::sort_key)
+            .first(&self.conn)
+            .optional()?)
+    }
+
+    /// Marks an element absent, or waiting again if it already is.
+    pub fn toggle_absent(
+        &self,
+        queue: &QueueKey,
+        elem: ElementRef,
+    ) -> super::error::Result<QueueElement> {
+        use super::error::Error;
+        use schema::queue_elements as qe;
+
+        self.conn.transaction::<_, Error, _>(|| {
+            let found = self.find_element(queue, elem)?;
+            let new_status = match found.status {
+                ElementStatus::Absent => ElementStatus::Waiting,
+                _ => ElementStatus::Absent,
+            };
+            let updated = diesel::update(qe::table.find(found.id))
+                .set(qe::status.eq(new_status))
+                .get_result(&self.conn)?;
+
+            self.bump_queue_version(queue)?;
+            Ok(updated)
+        })
+    }
+
+    /// Makes the first waiting or skipped element current, absent ones are passed over.
+    fn call_next(&self, queue: &QueueKey) -> super::error::Result<Option<QueueElement>> {
+        use schema::queue_elements as qe;
+
+        let next = qe::table
+            .filter(qe::queue_id.eq(queue.id))
+            .filter(qe::status.eq_any(vec![ElementStatus::Waiting, ElementStatus::Skipped]))
+            .order(qe::sort_key)
+            .first::<QueueElement>(&self.conn)
+            .optional()?;
+        Ok(match next {
+            Some(next) => Some(
+                diesel::update(qe::table.find(next.id))
+                    .set(qe::status.eq(ElementStatus::Current))
+                    .get_result::<QueueElement>(&self.conn)?,
+            ),
+            None => None,
+        })
+    }
+
+    fn move_elem_back(
+        &self,
+        queue: &QueueKey,
+        elem_id: i64,
+        places: Option<i32>,
+    ) -> super::error::Result<QueueElement> {
+        use super::error::Error;
+        use schema::queue_elements as qe;
+
+        let key = match self.back_sort_key(queue, elem_id, places)? {
+            Some(key) => key,
+            None => {
+                self.rebalance_queue(queue)?;
+                self.back_sort_key(queue, elem_id, places)?.ok_or_else(|| {
+                    Error::Wtf("No free sort key right after a rebalance.".to_string())
+                })?
+            }
+        };
+        Ok(diesel::update(qe::table.find(elem_id))
+            .set(qe::sort_key.eq(key))
+            .get_result(&self.conn)?)
+    }
+
+    /// Finds a sort key which puts the element `places` further, or last for `None`.
+    /// Returns `None` if the neighbouring keys are too close to each other.
+    fn back_sort_key(
+        &self,
+        queue: &QueueKey,
+        elem_id: i64,
+        places: Option<i32>,
+    ) -> super::error::Result<Option<i64>> {
+        use diesel::dsl::*;
+        use schema::queue_elements as qe;
+
+        let own_key = qe::table
+            .find(elem_id)
+            .select(qe::sort_key)
+            .first::<i64>(&self.conn)?;
+        let following = qe::table
+            .filter(qe::queue_id.eq(queue.id))
+            .filter(qe::sort_key.gt(own_key))
+            .select(qe::sort_key);
+
+        let (prev, next) = match places {
+            Some(places) => {
+                let keys = following
+                    .order(qe::sort_key)
+                    .limit(places as i64 + 1)
+                    .load::<i64>(&self.conn)?;
+                let prev = keys.get(places.max(1) as usize - 1).or_else(|| keys.last());
+                (prev.copied(), keys.get(places as usize).copied())
+            }
+            None => (
+                following
+                    .select(max(qe::sort_key))
+                    .first::<Option<i64>>(&self.conn)?,
+                None,
+            ),
+        };
+
+        Ok(match prev {
+            // already the last one
+            None => Some(own_key),
+            Some(prev) => key_between(Some(prev), next),
+        })
+    }
+
+    fn bump_queue_version(&self, queue: &QueueKey) -> super::error::Result<()> {
+        use schema::queues::dsl::*;
+
+        diesel::update(queues.find(queue.id))
+            .set(version.eq(version + 1))
+            .execute(&self.conn)?;
+        Ok(())
+    }
+
+    /// Finds a sort key which puts a new element at `index`, which can be one past the end.
+    /// Returns `None` if the neighbouring keys are too close to each other.
+    fn free_sort_key(
+        &self,
+        queue: &QueueKey,
+        index: Option<i32>,
+    ) -> super::error::Result<Option<i64>> {
+        use diesel::dsl::*;
+        use schema::queue_elements as qe;
+
+        let queue_keys = qe::table
+            .filter(qe::queue_id.eq(queue.id))
+            .select(qe::sort_key);
+        let last_key = || {
+            queue_keys
+                .select(max(qe::sort_key))
+                .first::<Option<i64>>(&self.conn)
+        };
+
+        let (prev, next) = match index {
+            Some(index) if index < 1 => {
+                return Err(super::error::Error::NonexistentPosition { pos: index })
+            }
+            Some(1) => (
+                None,
+                queue_keys
+                    .select(min(qe::sort_key))
+                    .first::<Option<i64>>(&self.conn)?,
+            ),
+            Some(index) => {
+                let keys = queue_keys
+                    .order(qe::sort_key)
+                    .offset(index as i64 - 2)
+                    .limit(2)
+                    .load::<i64>(&self.conn)?;
+                match keys.as_slice() {
+                    [prev, next] => (Some(*prev), Some(*next)),
+                    // right after the last element
+                    [prev] => (Some(*prev), None),
+                    _ => return Err(super::error::Error::NonexistentPosition { pos: index }),
+                }
+            }
+            None => (last_key()?, None),
+        };
 
-        Ok(deleted_name)
+        Ok(key_between(prev, next))
+    }
+
+    /// Spreads the sort keys of the queue evenly, keeping the order.
+    fn rebalance_queue(&self, queue: &QueueKey) -> super::error::Result<()> {
+        use diesel::sql_types::BigInt;
+
+        log::info!("Rebalancing sort keys of queue {}", queue.id);
+        diesel::sql_query(
+            "update queue_elements e set sort_key = r.place * $2 \
+             from (select id, row_number() over (order by sort_key) as place \
+                   from queue_elements where queue_id = $1) r \
+             where r.id = e.id",
+        )
+        .bind::<BigInt, _>(queue.id)
+        .bind::<BigInt, _>(SORT_KEY_STEP)
+        .execute(&self.conn)?;
+        Ok(())
     }
 
     pub fn set_queue_name(
@@ -235,14 +1343,222 @@ impl QueueRepository {
         queue: &QueueKey,
         new_name: String,
     ) -> super::error::Result<String> {
-        
         use schema::queues as q;
 
-        let new_name: Option<_> =
-            diesel::update(q::table.filter(q::chat_id.eq(queue.chat_id).and(q::id.eq(queue.id))))
-                .set(q::qname.eq(new_name))
-                .returning(q::qname)
-                .get_result(&self.conn)?;
+        let new_name: Option<_> = diesel::update(q::table.filter(q::id.eq(queue.id)))
+            .set((q::qname.eq(new_name), q::version.eq(q::version + 1)))
+            .returning(q::qname)
+            .get_result(&self.conn)?;
         Ok(new_name.unwrap())
     }
+
+    /// Sets or, for `None`, removes the time slots of the queue.
+    pub fn set_queue_slots(
+        &self,
+        queue: &QueueKey,
+        slots: Option<Slots>,
+    ) -> super::error::Result<Queue> {
+        use schema::queues as q;
+
+        Ok(diesel::update(q::table.find(queue.id))
+            .set((
+                q::starts_at.eq(slots.map(|x| x.starts_at)),
+                q::slot_minutes.eq(slots.map(|x| x.minutes)),
+                q::utc_offset.eq(slots.map_or(0, |x| x.utc_offset)),
+                q::version.eq(q::version + 1),
+            ))
+            .get_result(&self.conn)?)
+    }
+}
+
+fn with_places(elems: Vec<QueueElement>) -> Vec<QueueElementForQueue> {
+    elems
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| QueueElementForQueue {
+            element_name: x.element_name,
+            queue_place: i as i32 + 1,
+            id: x.id,
+            status: x.status,
+            user_id: x.user_id,
+            label: x.label,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::da::Error;
+
+    /// A repository whose changes are rolled back, on the database of `DATABASE_URL`.
+    pub(crate) fn test_repo() -> QueueRepository {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let conn =
+            PgConnection::establish(&database_url).expect("Error connecting to the database");
+        conn.begin_test_transaction()
+            .expect("Couldn't begin a test transaction");
+        diesel_migrations::run_pending_migrations(&conn).expect("Couldn't run migrations");
+        QueueRepository::from_connection(conn)
+    }
+
+    /// A queue of people with the names in a chat of its own.
+    pub(crate) fn test_queue(repo: &QueueRepository, names: &[&str]) -> Queue {
+        repo.get_or_create_chat(-1).unwrap();
+        let queue = repo
+            .create_new_queue(NewQueue {
+                chat_id: -1,
+                qname: None,
+            })
+            .unwrap();
+        let elements = names
+            .iter()
+            .map(|x| ElementData::named(x.to_string()))
+            .collect();
+        repo.insert_filled_queue(queue.key(), elements).unwrap();
+        queue
+    }
+
+    pub(crate) fn names(repo: &QueueRepository, queue: &Queue) -> Vec<String> {
+        repo.get_elements_for_queue(&queue.key())
+            .unwrap()
+            .into_iter()
+            .map(|x| x.element_name)
+            .collect()
+    }
+
+    #[test]
+    fn key_between_neighbours() {
+        assert_eq!(key_between(None, None), Some(SORT_KEY_STEP));
+        assert_eq!(key_between(Some(10), None), Some(10 + SORT_KEY_STEP));
+        assert_eq!(key_between(None, Some(10)), Some(10 - SORT_KEY_STEP));
+        assert_eq!(key_between(Some(10), Some(20)), Some(15));
+        assert_eq!(key_between(Some(10), Some(12)), Some(11));
+    }
+
+    #[test]
+    fn key_between_runs_out() {
+        assert_eq!(key_between(Some(10), Some(11)), None);
+        assert_eq!(key_between(Some(10), Some(10)), None);
+
+        // inserting at the same place keeps halving the gap after the previous element
+        let prev = SORT_KEY_STEP;
+        let mut next = 2 * SORT_KEY_STEP;
+        let mut inserts = 0;
+        while let Some(key) = key_between(Some(prev), Some(next)) {
+            assert!(prev < key && key < next);
+            next = key;
+            inserts += 1;
+        }
+        assert_eq!(inserts, 32);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn insert_rebalances_when_keys_run_out() {
+        let repo = test_repo();
+        let queue = test_queue(&repo, &["first", "last"]);
+
+        let inserted = (0..40).map(|i| i.to_string()).collect::<Vec<_>>();
+        for name in &inserted {
+            repo.insert_new_elem(&queue.key(), ElementData::named(name.clone()), Some(2))
+                .unwrap();
+        }
+
+        let mut expected = vec!["first".to_string()];
+        expected.extend(inserted.into_iter().rev());
+        expected.push("last".to_string());
+        assert_eq!(names(&repo, &queue), expected);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn insert_rejects_places_past_the_end() {
+        let repo = test_repo();
+        let queue = test_queue(&repo, &["a", "b"]);
+
+        for pos in [0, 4] {
+            let res = repo.insert_new_elem(&queue.key(), ElementData::named("x".into()), Some(pos));
+            assert!(matches!(res, Err(Error::NonexistentPosition { pos: p }) if p == pos));
+        }
+        repo.insert_new_elem(&queue.key(), ElementData::named("c".into()), Some(3))
+            .unwrap();
+        repo.insert_new_elem(&queue.key(), ElementData::named("z".into()), Some(1))
+            .unwrap();
+        assert_eq!(names(&repo, &queue), ["z", "a", "b", "c"]);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn message_versions_follow_edits() {
+        let repo = test_repo();
+        let queue = test_queue(&repo, &["a"]);
+        let added = repo
+            .add_queue_message(QueueMessage {
+                message_id: 1,
+                chat_id: -1,
+                queue_id: queue.id,
+                version: queue.version,
+                page: 0,
+            })
+            .unwrap();
+
+        // a message which couldn't be edited still shows the old places
+        repo.insert_new_elem(&queue.key(), ElementData::named("b".into()), Some(1))
+            .unwrap();
+        let changed = repo.get_queue(&queue.key()).unwrap();
+        let stale = repo.get_queue_message(-1, 1).unwrap().unwrap();
+        assert!(stale.version < changed.version);
+
+        repo.set_message_version(&added, changed.version).unwrap();
+        let edited = repo.get_queue_message(-1, 1).unwrap().unwrap();
+        assert_eq!(edited.version, changed.version);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn schedules_outlive_their_source_queue() {
+        let repo = test_repo();
+        let queue = test_queue(&repo, &["a", "b"]);
+        let people = || {
+            vec![
+                ElementData::named("a".into()),
+                ElementData::named("b".into()),
+            ]
+        };
+        let roster = repo.save_new_roster(-1, "Lab", people()).unwrap();
+        let copy = repo.save_new_roster(-1, "Lab", people()).unwrap();
+        assert_eq!(
+            (roster.name.as_str(), copy.name.as_str()),
+            ("Lab", "Lab (2)")
+        );
+
+        repo.create_schedule(NewScheduledQueue {
+            chat_id: -1,
+            roster_id: copy.id,
+            qname: "Lab {n}".into(),
+            next_run_at: Utc::now(),
+        })
+        .unwrap();
+        repo.delete_queue(&queue.key()).unwrap();
+        assert_eq!(repo.list_schedules(-1).unwrap().len(), 1);
+        assert_eq!(repo.get_roster_members(copy.id).unwrap().len(), 2);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn chosen_languages_leave_out_unset_chats() {
+        let repo = test_repo();
+        for chat in [-1, 1, 2] {
+            repo.get_or_create_chat(chat).unwrap();
+        }
+        let mut settings = repo.get_settings(1).unwrap();
+        settings.language = Some(Language::Uk);
+        repo.update_settings(&settings).unwrap();
+        repo.get_settings(2).unwrap();
+
+        let languages = repo.chosen_languages(&[1, 2, 3]).unwrap();
+        assert_eq!(languages.len(), 1);
+        assert_eq!(languages.get(&1), Some(&Language::Uk));
+    }
 }
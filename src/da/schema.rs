@@ -1,3 +1,59 @@
+table! {
+    /// Representation of the `chat_settings` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    chat_settings (chat_id) {
+        /// The `chat_id` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        chat_id -> Int8,
+        /// The `auto_pin` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        auto_pin -> Bool,
+        /// The `silent` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        silent -> Bool,
+        /// The `confirmations` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        confirmations -> Bool,
+        /// The `shuffle` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        shuffle -> Text,
+        /// The `language` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        language -> Nullable<Text>,
+        /// The `expiry_days` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expiry_days -> Nullable<Int4>,
+        /// The `expiry_action` column of the `chat_settings` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expiry_action -> Text,
+    }
+}
+
 table! {
     /// Representation of the `chats` table.
     ///
@@ -12,11 +68,81 @@ table! {
     }
 }
 
+table! {
+    /// Representation of the `pair_history` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    pair_history (chat_id, first_key, second_key) {
+        /// The `chat_id` column of the `pair_history` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        chat_id -> Int8,
+        /// The `first_key` column of the `pair_history` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        first_key -> Text,
+        /// The `second_key` column of the `pair_history` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        second_key -> Text,
+        /// The `times` column of the `pair_history` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        times -> Int4,
+    }
+}
+
+table! {
+    /// Representation of the `poll_votes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    poll_votes (poll_id, user_id) {
+        /// The `poll_id` column of the `poll_votes` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        poll_id -> Text,
+        /// The `user_id` column of the `poll_votes` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int8,
+        /// The `element_name` column of the `poll_votes` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        element_name -> Text,
+        /// The `option_ids` column of the `poll_votes` table.
+        ///
+        /// Its SQL type is `Array<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        option_ids -> Array<Int4>,
+        /// The `voted_at` column of the `poll_votes` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        voted_at -> Timestamptz,
+    }
+}
+
 table! {
     /// Representation of the `queue_elements` table.
     ///
     /// (Automatically generated by Diesel.)
-    queue_elements (queue_place, queue_id, chat_id) {
+    queue_elements (id) {
         /// The `element_name` column of the `queue_elements` table.
         ///
         /// Its SQL type is `Varchar`.
@@ -29,18 +155,154 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         queue_id -> Int8,
-        /// The `chat_id` column of the `queue_elements` table.
+        /// The `sort_key` column of the `queue_elements` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        sort_key -> Int8,
+        /// The `id` column of the `queue_elements` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `status` column of the `queue_elements` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        status -> Text,
+        /// The `user_id` column of the `queue_elements` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Nullable<Int8>,
+        /// The `created_at` column of the `queue_elements` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `updated_at` column of the `queue_elements` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+        /// The `label` column of the `queue_elements` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        label -> Nullable<Text>,
+    }
+}
+
+table! {
+    /// Representation of the `queue_messages` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    queue_messages (message_id, chat_id) {
+        /// The `message_id` column of the `queue_messages` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        message_id -> Int8,
+        /// The `chat_id` column of the `queue_messages` table.
         ///
         /// Its SQL type is `Int8`.
         ///
         /// (Automatically generated by Diesel.)
         chat_id -> Int8,
-        /// The `queue_place` column of the `queue_elements` table.
+        /// The `queue_id` column of the `queue_messages` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        queue_id -> Int8,
+        /// The `version` column of the `queue_messages` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Int4,
+        /// The `page` column of the `queue_messages` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        queue_place -> Int4,
+        page -> Int4,
+    }
+}
+
+table! {
+    /// Representation of the `queue_templates` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    queue_templates (chat_id) {
+        /// The `chat_id` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        chat_id -> Int8,
+        /// The `numbering` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        numbering -> Text,
+        /// The `waiting_mark` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        waiting_mark -> Text,
+        /// The `current_mark` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        current_mark -> Text,
+        /// The `done_mark` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        done_mark -> Text,
+        /// The `header` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        header -> Text,
+        /// The `footer` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        footer -> Text,
+        /// The `show_counts` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        show_counts -> Bool,
+        /// The `skipped_mark` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        skipped_mark -> Text,
+        /// The `absent_mark` column of the `queue_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        absent_mark -> Text,
     }
 }
 
@@ -48,7 +310,7 @@ table! {
     /// Representation of the `queues` table.
     ///
     /// (Automatically generated by Diesel.)
-    queues (id, chat_id) {
+    queues (id) {
         /// The `id` column of the `queues` table.
         ///
         /// Its SQL type is `Int8`.
@@ -67,10 +329,332 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         qname -> Nullable<Text>,
+        /// The `version` column of the `queues` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Int4,
+        /// The `created_at` column of the `queues` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `archived` column of the `queues` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        archived -> Bool,
+        /// The `updated_at` column of the `queues` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+        /// The `starts_at` column of the `queues` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        starts_at -> Nullable<Timestamptz>,
+        /// The `slot_minutes` column of the `queues` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        slot_minutes -> Nullable<Int4>,
+        /// The `utc_offset` column of the `queues` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        utc_offset -> Int4,
+        /// The `sheet` column of the `queues` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        sheet -> Bool,
+    }
+}
+
+table! {
+    /// Representation of the `roster_members` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    roster_members (roster_id, position) {
+        /// The `roster_id` column of the `roster_members` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        roster_id -> Int8,
+        /// The `position` column of the `roster_members` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        position -> Int4,
+        /// The `element_name` column of the `roster_members` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        element_name -> Text,
+        /// The `user_id` column of the `roster_members` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Nullable<Int8>,
+    }
+}
+
+table! {
+    /// Representation of the `rosters` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    rosters (id) {
+        /// The `id` column of the `rosters` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `chat_id` column of the `rosters` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        chat_id -> Int8,
+        /// The `name` column of the `rosters` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Text,
+        /// The `created_at` column of the `rosters` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `updated_at` column of the `rosters` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    /// Representation of the `scheduled_queues` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    scheduled_queues (id) {
+        /// The `id` column of the `scheduled_queues` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `chat_id` column of the `scheduled_queues` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        chat_id -> Int8,
+        /// The `qname` column of the `scheduled_queues` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        qname -> Text,
+        /// The `next_number` column of the `scheduled_queues` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        next_number -> Int4,
+        /// The `next_run_at` column of the `scheduled_queues` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        next_run_at -> Timestamptz,
+        /// The `roster_id` column of the `scheduled_queues` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        roster_id -> Int8,
+    }
+}
+
+table! {
+    /// Representation of the `signup_entries` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    signup_entries (signup_id, user_id) {
+        /// The `signup_id` column of the `signup_entries` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        signup_id -> Int8,
+        /// The `user_id` column of the `signup_entries` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int8,
+        /// The `element_name` column of the `signup_entries` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        element_name -> Text,
+        /// The `signed_up_at` column of the `signup_entries` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        signed_up_at -> Timestamptz,
+    }
+}
+
+table! {
+    /// Representation of the `signups` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    signups (id) {
+        /// The `id` column of the `signups` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `chat_id` column of the `signups` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        chat_id -> Int8,
+        /// The `message_id` column of the `signups` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        message_id -> Nullable<Int8>,
+        /// The `qname` column of the `signups` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        qname -> Nullable<Text>,
+        /// The `shuffle` column of the `signups` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        shuffle -> Bool,
+        /// The `closes_at` column of the `signups` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        closes_at -> Timestamptz,
+        /// The `closed` column of the `signups` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        closed -> Bool,
+    }
+}
+
+table! {
+    /// Representation of the `swap_requests` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    swap_requests (id) {
+        /// The `id` column of the `swap_requests` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `queue_id` column of the `swap_requests` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        queue_id -> Int8,
+        /// The `chat_id` column of the `swap_requests` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        chat_id -> Int8,
+        /// The `message_id` column of the `swap_requests` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        message_id -> Nullable<Int8>,
+        /// The `from_element_id` column of the `swap_requests` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        from_element_id -> Int8,
+        /// The `to_element_id` column of the `swap_requests` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        to_element_id -> Int8,
+        /// The `expires_at` column of the `swap_requests` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expires_at -> Timestamptz,
     }
 }
 
-joinable!(queue_elements -> chats (chat_id));
+joinable!(chat_settings -> chats (chat_id));
+joinable!(pair_history -> chats (chat_id));
+joinable!(queue_elements -> queues (queue_id));
+joinable!(queue_messages -> chats (chat_id));
+joinable!(queue_messages -> queues (queue_id));
+joinable!(queue_templates -> chats (chat_id));
 joinable!(queues -> chats (chat_id));
+joinable!(roster_members -> rosters (roster_id));
+joinable!(rosters -> chats (chat_id));
+joinable!(scheduled_queues -> chats (chat_id));
+joinable!(scheduled_queues -> rosters (roster_id));
+joinable!(signup_entries -> signups (signup_id));
+joinable!(signups -> chats (chat_id));
+joinable!(swap_requests -> chats (chat_id));
+joinable!(swap_requests -> queues (queue_id));
 
-allow_tables_to_appear_in_same_query!(chats, queue_elements, queues,);
+allow_tables_to_appear_in_same_query!(
+    chat_settings,
+    chats,
+    pair_history,
+    poll_votes,
+    queue_elements,
+    queue_messages,
+    queue_templates,
+    queues,
+    roster_members,
+    rosters,
+    scheduled_queues,
+    signup_entries,
+    signups,
+    swap_requests,
+);
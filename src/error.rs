@@ -11,10 +11,20 @@ pub enum Error {
     TeloxideRequest(#[from] teloxide::RequestError),
     #[error("Teloxide download error")]
     TeloxideDonload(#[from] teloxide::DownloadError),
+    #[error("Waiting for a lock failed")]
+    Join(#[from] tokio::task::JoinError),
     #[error("Invalid UTF")]
     Utf(#[from] Utf8Error),
     #[error("No queue reply.")]
     NoQueueReply,
+    #[error("The queue has changed since the user saw it.")]
+    OutdatedQueue { queue_id: i64, version: i32 },
+    #[error("The queue is archived.")]
+    ArchivedQueue,
+    #[error("The slots of a sheet can't be moved.")]
+    SheetQueue,
+    #[error("No roster named {name}.")]
+    NoRoster { name: String },
 }
 
 pub type Result<T> = result::Result<T, Error>;
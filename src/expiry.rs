@@ -0,0 +1,146 @@
+use chrono::Utc;
+use std::time::Duration;
+use teloxide::{payloads::SendMessageSetters, prelude::*};
+
+use crate::{da, error, i18n, i18n::Msg, locks::QueueLocks};
+
+/// How often queues are checked for expiry.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Checks every hour for queues idle for longer than their chat allows
+/// and archives or deletes them. Never returns.
+pub async fn run(bot: Bot, locks: QueueLocks) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = expire_queues(&bot, &locks).await {
+            log::error!("Couldn't expire queues: {}", e);
+        }
+    }
+}
+
+async fn expire_queues(bot: &Bot, locks: &QueueLocks) -> error::Result<()> {
+    let expired = {
+        let repo = da::QueueRepository::from_connection(crate::establish_connection());
+        repo.expired_queues()?
+    };
+
+    for queue in expired {
+        if let Err(e) = expire_queue(bot, locks, queue.queue_id).await {
+            log::error!("Couldn't expire queue {}: {}", queue.queue_id, e);
+        }
+    }
+    Ok(())
+}
+
+/// What to do with a queue idle for `idle`, `None` while its chat keeps it as it is.
+/// Archived queues are only ever deleted.
+fn expiry_action(
+    settings: &da::ChatSettings,
+    archived: bool,
+    idle: chrono::Duration,
+) -> Option<da::ExpiryAction> {
+    let days = settings.expiry_days?;
+    if idle <= chrono::Duration::days(days.into()) {
+        return None;
+    }
+    match settings.expiry_action {
+        da::ExpiryAction::Archive if archived => None,
+        action => Some(action),
+    }
+}
+
+async fn expire_queue(bot: &Bot, locks: &QueueLocks, queue_id: i64) -> error::Result<()> {
+    let repo = da::QueueRepository::from_connection(crate::establish_connection());
+    let key = da::QueueKey { id: queue_id };
+
+    let _guard = locks.lock(queue_id).await?;
+
+    // the queue could have been changed or removed while waiting for the lock
+    let queue = match repo.find_queue(&key)? {
+        Some(queue) => queue,
+        None => return Ok(()),
+    };
+    let settings = repo.get_settings(queue.chat_id)?;
+    let idle = Utc::now() - queue.updated_at;
+    let (days, action) = match expiry_action(&settings, queue.archived, idle) {
+        Some(action) => (settings.expiry_days.unwrap_or_default(), action),
+        None => return Ok(()),
+    };
+
+    let messages = repo.get_queue_messages(&key)?;
+    match action {
+        da::ExpiryAction::Archive => repo.archive_queue(&key)?,
+        da::ExpiryAction::Delete => repo.delete_queue(&key)?,
+    };
+    log::info!("Queue {} expired after {} days", queue.id, days);
+
+    crate::unpin_queue_messages(bot, &messages).await;
+
+    let lang = i18n::resolve(settings.language, None);
+    let text = Msg::QueueExpired {
+        name: queue.qname.as_deref(),
+        days,
+        action,
+    };
+    bot.send_message(queue.chat_id, text.text(lang))
+        .disable_notification(settings.silent)
+        .send()
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn settings(expiry_days: Option<i32>, expiry_action: da::ExpiryAction) -> da::ChatSettings {
+        da::ChatSettings {
+            chat_id: 1,
+            auto_pin: false,
+            silent: false,
+            confirmations: false,
+            shuffle: da::ShuffleMode::Keep,
+            language: None,
+            expiry_days,
+            expiry_action,
+        }
+    }
+
+    #[test]
+    fn queues_expire_after_the_chat_limit() {
+        let archive = settings(Some(7), da::ExpiryAction::Archive);
+        assert_eq!(expiry_action(&archive, false, Duration::days(7)), None);
+        assert_eq!(
+            expiry_action(&archive, false, Duration::days(7) + Duration::minutes(1)),
+            Some(da::ExpiryAction::Archive)
+        );
+
+        let delete = settings(Some(30), da::ExpiryAction::Delete);
+        assert_eq!(expiry_action(&delete, false, Duration::days(29)), None);
+        assert_eq!(
+            expiry_action(&delete, false, Duration::days(31)),
+            Some(da::ExpiryAction::Delete)
+        );
+    }
+
+    #[test]
+    fn queues_never_expire_without_a_limit() {
+        let never = settings(None, da::ExpiryAction::Delete);
+        assert_eq!(expiry_action(&never, false, Duration::days(10000)), None);
+        assert_eq!(expiry_action(&never, true, Duration::days(10000)), None);
+    }
+
+    #[test]
+    fn archived_queues_are_only_deleted() {
+        let archive = settings(Some(7), da::ExpiryAction::Archive);
+        assert_eq!(expiry_action(&archive, true, Duration::days(8)), None);
+
+        let delete = settings(Some(7), da::ExpiryAction::Delete);
+        assert_eq!(
+            expiry_action(&delete, true, Duration::days(8)),
+            Some(da::ExpiryAction::Delete)
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+/// Random orders tried before constraints are considered impossible.
+const ATTEMPTS: usize = 20;
+
+/// How many teams `/groups` makes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Count {
+    Groups(usize),
+    /// Teams of about this size.
+    Size(usize),
+}
+
+/// Options of `/groups`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupArgs {
+    pub count: Count,
+    /// Spreads people with the same attribute, written like `Ann (KA-01)`, over the teams.
+    pub balance: bool,
+    /// Saves every team as its own queue.
+    pub save: bool,
+}
+
+/// Parses `N|size=K [balance] [save]`.
+pub fn parse(arg: &str) -> Option<GroupArgs> {
+    let mut count = None;
+    let mut balance = false;
+    let mut save = false;
+    for word in arg.split_whitespace() {
+        match word {
+            "balance" => balance = true,
+            "save" => save = true,
+            _ => {
+                let parsed = match word.strip_prefix("size=") {
+                    Some(size) => Count::Size(size.parse().ok()?),
+                    None => Count::Groups(word.parse().ok()?),
+                };
+                if count.replace(parsed).is_some() {
+                    return None;
+                }
+            }
+        }
+    }
+
+    match count? {
+        Count::Groups(0) | Count::Size(0) => None,
+        count => Some(GroupArgs {
+            count,
+            balance,
+            save,
+        }),
+    }
+}
+
+impl Count {
+    /// The number of teams for `people`, never more than there are people.
+    pub fn groups(self, people: usize) -> usize {
+        let groups = match self {
+            Count::Groups(groups) => groups,
+            Count::Size(size) => people.div_ceil(size),
+        };
+        groups.clamp(1, people.max(1))
+    }
+}
+
+/// People who have to end up in the same team, or in different ones.
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    pub together: Vec<Vec<String>>,
+    pub apart: Vec<Vec<String>>,
+}
+
+/// Parses lines like `together: Ann, Bob` and `apart: Carl, Dan`.
+pub fn parse_constraints<'a>(lines: impl Iterator<Item = &'a str>) -> Option<Constraints> {
+    let mut constraints = Constraints::default();
+    for line in lines {
+        let (kind, names) = line.split_once(':')?;
+        let names = names
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        match kind.trim() {
+            "together" => constraints.together.push(names),
+            "apart" => constraints.apart.push(names),
+            _ => return None,
+        }
+    }
+    Some(constraints)
+}
+
+/// The text in the parentheses at the end of a name, like `KA-01` in `Ann (KA-01)`.
+pub fn attribute(name: &str) -> Option<&str> {
+    let name = name.trim_end().strip_suffix(')')?;
+    let start = name.rfind('(')?;
+    Some(name[start + 1..].trim())
+}
+
+/// The name without its attribute.
+fn base_name(name: &str) -> &str {
+    match attribute(name) {
+        Some(_) => name[..name.rfind('(').unwrap_or(name.len())].trim(),
+        None => name.trim(),
+    }
+}
+
+/// Finds a person by name regardless of case, the attribute can be left out.
+pub fn find_person(names: &[String], name: &str) -> Option<usize> {
+    let name = name.trim().to_lowercase();
+    names
+        .iter()
+        .position(|x| x.trim().to_lowercase() == name || base_name(x).to_lowercase() == name)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SplitError {
+    /// A name of the constraints isn't in the queue.
+    Unknown(String),
+    /// The two people have to be both together and apart.
+    Conflict(String, String),
+    /// Keeping everyone apart as asked takes more teams.
+    Impossible,
+}
+
+/// Splits people into `groups` random teams of sizes as even as the constraints allow.
+/// Returns indices into `names`.
+pub fn split(
+    names: &[String],
+    groups: usize,
+    constraints: &Constraints,
+    balance: bool,
+) -> Result<Vec<Vec<usize>>, SplitError> {
+    let index =
+        |name: &String| find_person(names, name).ok_or_else(|| SplitError::Unknown(name.clone()));
+
+    // people kept together are placed as one unit
+    let mut unit_of = (0..names.len()).collect::<Vec<_>>();
+    for together in &constraints.together {
+        let people = together.iter().map(index).collect::<Result<Vec<_>, _>>()?;
+        for pair in people.windows(2) {
+            let (from, to) = (unit_of[pair[1]], unit_of[pair[0]]);
+            unit_of
+                .iter_mut()
+                .filter(|x| **x == from)
+                .for_each(|x| *x = to);
+        }
+    }
+    let mut apart = Vec::new();
+    for set in &constraints.apart {
+        let people = set.iter().map(index).collect::<Result<Vec<_>, _>>()?;
+        for (i, a) in people.iter().enumerate() {
+            apart.extend(people[i + 1..].iter().map(|b| (*a, *b)));
+        }
+    }
+    // no order of the units can fix this, so it isn't left to the attempts
+    if let Some((a, b)) = apart.iter().find(|(a, b)| unit_of[*a] == unit_of[*b]) {
+        return Err(SplitError::Conflict(names[*a].clone(), names[*b].clone()));
+    }
+
+    let mut units = HashMap::<usize, Vec<usize>>::new();
+    for (person, unit) in unit_of.iter().enumerate() {
+        units.entry(*unit).or_default().push(person);
+    }
+    let units = units.into_values().collect::<Vec<_>>();
+    let attribute_of = |person: usize| {
+        if balance {
+            attribute(&names[person])
+        } else {
+            None
+        }
+    };
+
+    for _ in 0..ATTEMPTS {
+        if let Some(teams) = try_split(names.len(), units.clone(), groups, &apart, &attribute_of) {
+            return Ok(teams);
+        }
+    }
+    Err(SplitError::Impossible)
+}
+
+/// Deals the units out one by one, each to the emptiest team it can join,
+/// preferring the one with the fewest people of its attribute.
+fn try_split<'a>(
+    people: usize,
+    mut units: Vec<Vec<usize>>,
+    groups: usize,
+    apart: &[(usize, usize)],
+    attribute_of: &impl Fn(usize) -> Option<&'a str>,
+) -> Option<Vec<Vec<usize>>> {
+    let mut rng = rand::rngs::OsRng;
+    units.shuffle(&mut rng);
+    // the biggest units go first while there is room, people with the same attribute
+    // are dealt one after another so they go round the teams
+    units.sort_by_key(|x| (std::cmp::Reverse(x.len()), attribute_of(x[0])));
+
+    let capacity = people.div_ceil(groups);
+    let mut teams = vec![Vec::new(); groups];
+    let mut order = (0..groups).collect::<Vec<_>>();
+    for unit in units {
+        order.shuffle(&mut rng);
+        let attr = attribute_of(unit[0]);
+        let same_attr = |team: &Vec<usize>| {
+            team.iter()
+                .filter(|x| attr.is_some() && attribute_of(**x) == attr)
+                .count()
+        };
+        let fits = |team: &Vec<usize>| {
+            !apart.iter().any(|(a, b)| {
+                (unit.contains(a) && team.contains(b)) || (unit.contains(b) && team.contains(a))
+            })
+        };
+
+        let best = order
+            .iter()
+            .copied()
+            .filter(|x| fits(&teams[*x]))
+            .min_by_key(|x| {
+                let team = &teams[*x];
+                (
+                    team.len() + unit.len() > capacity,
+                    team.len(),
+                    same_attr(team),
+                )
+            })?;
+        teams[best].extend(unit);
+    }
+    Some(teams)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn constraints(together: &[&[&str]], apart: &[&[&str]]) -> Constraints {
+        let sets = |sets: &[&[&str]]| sets.iter().map(|x| people(x)).collect();
+        Constraints {
+            together: sets(together),
+            apart: sets(apart),
+        }
+    }
+
+    fn team_of(teams: &[Vec<usize>], person: usize) -> usize {
+        teams.iter().position(|x| x.contains(&person)).unwrap()
+    }
+
+    #[test]
+    fn parses_arguments() {
+        assert_eq!(
+            parse("3 balance"),
+            Some(GroupArgs {
+                count: Count::Groups(3),
+                balance: true,
+                save: false,
+            })
+        );
+        assert_eq!(parse("size=4 save").unwrap().count, Count::Size(4));
+        assert_eq!(parse("0"), None);
+        assert_eq!(parse("size=0"), None);
+        assert_eq!(parse("size=x"), None);
+        assert_eq!(parse("3 size=2"), None);
+        assert_eq!(parse("2 3"), None);
+        assert_eq!(parse("balance"), None);
+    }
+
+    #[test]
+    fn counts_teams() {
+        assert_eq!(Count::Size(3).groups(10), 4);
+        assert_eq!(Count::Size(5).groups(10), 2);
+        assert_eq!(Count::Groups(5).groups(3), 3);
+        assert_eq!(Count::Groups(2).groups(0), 1);
+    }
+
+    #[test]
+    fn splits_into_even_teams() {
+        let names = (0..10).map(|x| x.to_string()).collect::<Vec<_>>();
+        let teams = split(&names, 3, &Constraints::default(), false).unwrap();
+        let mut sizes = teams.iter().map(Vec::len).collect::<Vec<_>>();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [3, 3, 4]);
+
+        let mut everyone = teams.concat();
+        everyone.sort_unstable();
+        assert_eq!(everyone, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn keeps_people_together_and_apart() {
+        let names = people(&["Ann", "Bob", "Carl", "Dan", "Eve", "Fay"]);
+        let constraints = constraints(&[&["ann", "Bob"]], &[&["Ann", "Carl"], &["Bob", "Dan"]]);
+        for _ in 0..20 {
+            let teams = split(&names, 2, &constraints, false).unwrap();
+            assert_eq!(team_of(&teams, 0), team_of(&teams, 1));
+            assert_ne!(team_of(&teams, 0), team_of(&teams, 2));
+            assert_ne!(team_of(&teams, 1), team_of(&teams, 3));
+        }
+    }
+
+    #[test]
+    fn balances_attributes() {
+        let names = people(&[
+            "Ann (KA-01)",
+            "Bob (KA-01)",
+            "Carl (KA-01)",
+            "Dan (KA-02)",
+            "Eve (KA-02)",
+            "Fay (KA-02)",
+        ]);
+        for _ in 0..20 {
+            let teams = split(&names, 3, &Constraints::default(), true).unwrap();
+            for team in &teams {
+                let attributes = team
+                    .iter()
+                    .map(|x| attribute(&names[*x]))
+                    .collect::<Vec<_>>();
+                assert_eq!(team.len(), 2);
+                assert_ne!(attributes[0], attributes[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_people_without_their_attribute() {
+        let names = people(&["Ann (KA-01)", "Bob"]);
+        assert_eq!(attribute(&names[0]), Some("KA-01"));
+        assert_eq!(find_person(&names, "ann"), Some(0));
+        assert_eq!(find_person(&names, "Ann (KA-01)"), Some(0));
+        assert_eq!(find_person(&names, "Carl"), None);
+    }
+
+    #[test]
+    fn fails_when_more_people_are_apart_than_teams() {
+        let names = people(&["Ann", "Bob", "Carl", "Dan"]);
+        let constraints = constraints(&[], &[&["Ann", "Bob", "Carl"]]);
+        assert_eq!(
+            split(&names, 2, &constraints, false),
+            Err(SplitError::Impossible)
+        );
+    }
+
+    #[test]
+    fn reports_unknown_people() {
+        let names = people(&["Ann", "Bob"]);
+        let constraints = constraints(&[&["Ann", "Zed"]], &[]);
+        assert_eq!(
+            split(&names, 2, &constraints, false),
+            Err(SplitError::Unknown("Zed".to_string()))
+        );
+    }
+
+    #[test]
+    fn reports_people_both_together_and_apart() {
+        let names = people(&["Ann", "Bob", "Carl", "Dan"]);
+        // Ann and Carl are together through Bob
+        let constraints = constraints(&[&["Ann", "Bob"], &["Bob", "Carl"]], &[&["Carl", "Ann"]]);
+        assert_eq!(
+            split(&names, 2, &constraints, false),
+            Err(SplitError::Conflict("Carl".to_string(), "Ann".to_string()))
+        );
+    }
+}
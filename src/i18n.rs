@@ -0,0 +1,1362 @@
+use crate::da::{ChatSettings, ExpiryAction, Language, ShuffleMode};
+
+/// Picks the language of a chat, chats which didn't choose one
+/// get the language of the user's telegram client.
+pub fn resolve(chosen: Option<Language>, language_code: Option<&str>) -> Language {
+    chosen
+        .or_else(|| language_code.and_then(from_code))
+        .unwrap_or(Language::En)
+}
+
+/// The language of messages everyone in the chat sees, like rendered queues,
+/// so they don't depend on who touched them last.
+pub fn chat_language(settings: &ChatSettings) -> Language {
+    resolve(settings.language, None)
+}
+
+fn from_code(code: &str) -> Option<Language> {
+    // codes are IETF tags like `en-US`
+    match code.split('-').next()? {
+        "en" => Some(Language::En),
+        "uk" => Some(Language::Uk),
+        _ => None,
+    }
+}
+
+/// The command list answered to `/help`.
+pub fn help(lang: Language) -> String {
+    let (title, commands) = match lang {
+        Language::En => ("These commands are supported:", EN_COMMANDS),
+        Language::Uk => ("Підтримуються такі команди:", UK_COMMANDS),
+    };
+
+    let mut text = title.to_string();
+    for (command, description) in commands {
+        text.push_str(&format!("\n/{} — {}", command, description));
+    }
+    text
+}
+
+const EN_COMMANDS: &[(&str, &str)] = &[
+    ("help", "Obtain help."),
+    (
+        "swap",
+        "Swap positions in the queue, only for chat admins. \
+         Syntax: <b>/swap</b> <u>place</u> <u>place</u>",
+    ),
+    (
+        "trade",
+        "Ask the owner of the element at the place to trade places with you, \
+         the places are swapped only once they accept. Syntax: <b>/trade</b> <u>place</u>",
+    ),
+    (
+        "queuerand",
+        "Create a new queue from another queue with shuffling. Syntax: <b>/queuerand</b> <u>[qname]</u> <u>[^roster]</u>. \
+         With a roster the queue is made of its members instead.",
+    ),
+    (
+        "queuefile",
+        "Create a new queue from a file, shuffled if the chat settings say so. Syntax: <b>/queuefile</b> <u>[qname]</u> <u>[^roster]</u>. \
+         With a roster the queue is made of its members instead.",
+    ),
+    (
+        "insert",
+        "Add an element to a queue. Syntax: <b>/insert</b> <u>[name]</u> <u>[^place]</u>. \
+         If place isn't provided then inserts to the end of a queue. \
+         If name isn't provided then inserts you.",
+    ),
+    (
+        "remove",
+        "Remove an element from a queue. Syntax: <b>/remove</b> <u>place</u>",
+    ),
+    (
+        "qname",
+        "Set a new name for the queue. Syntax: <b>/qname</b> <u>new_name</u>",
+    ),
+    (
+        "slots",
+        "Give every element of the queue a time slot in turn, the start times are shown in the queue. \
+         Syntax: <b>/slots</b> <u>[date]</u> <u>time</u> <u>minutes</u>, like <b>/slots</b> 2021-10-12 10:00+03 10. \
+         Without a date the slots start today, <b>/slots off</b> removes them.",
+    ),
+    (
+        "calendar",
+        "Send the time slots of the queue as a calendar file. Syntax: <b>/calendar</b>",
+    ),
+    (
+        "sheet",
+        "Post a booking sheet with free slots for people to claim, admins only. \
+         Syntax: <b>/sheet</b> <u>[name]</u> with a time or topic of a slot on every next line, \
+         or <b>/sheet</b> <u>count</u> <u>[name]</u> for numbered slots.",
+    ),
+    (
+        "claim",
+        "Claim a free slot of a sheet, one per person. Syntax: <b>/claim</b> <u>place</u>",
+    ),
+    (
+        "unclaim",
+        "Free your slot in a sheet. Syntax: <b>/unclaim</b>",
+    ),
+    (
+        "assign",
+        "Randomly give everyone in the queue a topic, in a new queue. \
+         Syntax: <b>/assign</b> <u>[unique]</u> <u>[seed=N]</u> <u>[name]</u> <u>[^list]</u> with the topics on the next lines, \
+         or taken from the roster or the queue named after ^. Topics are shared evenly when there are fewer of them than people, \
+         <b>unique</b> forbids sharing, the same <b>seed</b> gives the same result.",
+    ),
+    (
+        "groups",
+        "Split the queue into random teams of even sizes. \
+         Syntax: <b>/groups</b> <u>N</u> or <b>/groups</b> size=<u>K</u>, then <u>[balance]</u> <u>[save]</u>. \
+         Lines like together: Ann, Bob and apart: Carl, Dan after the command keep people in one team or in different ones. \
+         <b>balance</b> spreads people with the same group in parentheses, like Ann (KA-01), over the teams, \
+         <b>save</b> makes every team a queue.",
+    ),
+    (
+        "pairs",
+        "Pair up the people in the queue so that everyone meets everyone before any pair repeats. \
+         Syntax: <b>/pairs</b>, with an odd count somebody has a bye. \
+         <b>/pairs reset</b> forgets the pairs of the chat, admins only.",
+    ),
+    (
+        "santa",
+        "Draw a Secret Santa among the people in the queue, admins only. \
+         Syntax: <b>/santa</b> <u>[note]</u>, with lines like exclude: Ann, Bob after it for people who shouldn't draw each other. \
+         Everyone gets their pick and the note in a private message, so they have to join the queue themselves \
+         and start a private chat with the bot first.",
+    ),
+    (
+        "repost",
+        "Post the queue again as a new message. Syntax: <b>/repost</b>",
+    ),
+    (
+        "show",
+        "Post the queue opened at a page. Syntax: <b>/show</b> <u>[page]</u>. \
+         <b>/show all</b> sends the whole queue as a file.",
+    ),
+    (
+        "next",
+        "Mark the current element as done and call the next one, absent ones are passed over. Syntax: <b>/next</b>",
+    ),
+    (
+        "skip",
+        "Move the current element to the end, or the given number of places back, and call the next one. \
+         Syntax: <b>/skip</b> <u>[places]</u>",
+    ),
+    (
+        "postpone",
+        "Move yourself to the end of the queue, or the given number of places back. \
+         Syntax: <b>/postpone</b> <u>[places]</u>",
+    ),
+    (
+        "absent",
+        "Mark an element as absent, it stays in the queue but isn't called. Run it again to bring the element back. \
+         Syntax: <b>/absent</b> <u>[place]</u>, without a place it marks you.",
+    ),
+    (
+        "template",
+        "Change how queues of the chat look, admins only. Syntax: <b>/template</b> <u>option</u> <u>value</u>. \
+         Options: <b>numbering</b> paren|dot|hash|none, <b>waiting</b>, <b>current</b>, <b>done</b>, <b>skipped</b>, <b>absent</b> <u>[mark]</u>, \
+         <b>header</b>, <b>footer</b> <u>[text]</u> where {name} is the queue name, <b>counts</b> on|off. \
+         <b>/template reset</b> restores the defaults, <b>/template</b> shows the current one.",
+    ),
+    (
+        "settings",
+        "Show the settings of the chat, admins can change them. Syntax: <b>/settings</b>",
+    ),
+    (
+        "list",
+        "List the queues of the chat, newest first. Syntax: <b>/list</b>",
+    ),
+    (
+        "archive",
+        "Archive the queue, admins only. It is kept but can't be changed and isn't listed. Syntax: <b>/archive</b>",
+    ),
+    (
+        "delete",
+        "Delete the queue with all its elements, admins only. Syntax: <b>/delete</b>",
+    ),
+    (
+        "schedule",
+        "Create a shuffled copy of the queue every week, admins only. \
+         Syntax: <b>/schedule</b> <u>day</u> <u>HH:MM[+offset]</u> <u>name</u> <u>[^roster]</u>, \
+         where {n} in the name is the number of the copy. The time is in UTC unless an offset is given. \
+         The people of the queue are saved as a roster for the schedule, \
+         with a roster its members are shuffled instead. <b>/schedule</b> lists the scheduled queues.",
+    ),
+    (
+        "signup",
+        "Collect people for a new queue with a button until the time runs out. \
+         Syntax: <b>/signup</b> <u>[name]</u> <u>duration</u> <u>[shuffle]</u>, the duration is like 10m, 2h or 1d. \
+         The queue keeps the sign-up order unless shuffle is given.",
+    ),
+    (
+        "poll",
+        "Send a poll whose voters can be made into queues. \
+         Syntax: <b>/poll</b> followed by the question and the options on separate lines.",
+    ),
+    (
+        "queuefrompoll",
+        "Create queues of the voters for every option of a poll sent with /poll, in the order they voted. \
+         Syntax: <b>/queuefrompoll</b> <u>[option]</u>, where the option is the number of a single option.",
+    ),
+    (
+        "roster",
+        "Keep named lists of people to create queues from. \
+         <b>/roster save</b> <u>name</u> saves the queue or the file the command replies to, \
+         or the members written on the lines after the name. \
+         <b>/roster edit</b> <u>name</u> adds the members on the lines after the name, lines starting with - remove them. \
+         <b>/roster list</b>, <b>/roster show</b> <u>name</u>, <b>/roster delete</b> <u>name</u>. Only admins can change rosters.",
+    ),
+];
+
+const UK_COMMANDS: &[(&str, &str)] = &[
+    ("help", "Отримати довідку."),
+    (
+        "swap",
+        "Поміняти місця в черзі, лише для адміністраторів чату. \
+         Синтаксис: <b>/swap</b> <u>місце</u> <u>місце</u>",
+    ),
+    (
+        "trade",
+        "Попросити власника елемента на місці помінятися з вами, \
+         місця міняються лише після його згоди. Синтаксис: <b>/trade</b> <u>місце</u>",
+    ),
+    (
+        "queuerand",
+        "Створити нову чергу з іншої черги, перемішавши її. Синтаксис: <b>/queuerand</b> <u>[назва]</u> <u>[^список]</u>. \
+         Зі списком черга складається з його учасників.",
+    ),
+    (
+        "queuefile",
+        "Створити нову чергу з файлу, перемішавши її, якщо так вказано в налаштуваннях чату. Синтаксис: <b>/queuefile</b> <u>[назва]</u> <u>[^список]</u>. \
+         Зі списком черга складається з його учасників.",
+    ),
+    (
+        "insert",
+        "Додати елемент до черги. Синтаксис: <b>/insert</b> <u>[ім'я]</u> <u>[^місце]</u>. \
+         Якщо місце не вказане, додає в кінець черги. \
+         Якщо ім'я не вказане, додає вас.",
+    ),
+    (
+        "remove",
+        "Видалити елемент із черги. Синтаксис: <b>/remove</b> <u>місце</u>",
+    ),
+    (
+        "qname",
+        "Задати нову назву черги. Синтаксис: <b>/qname</b> <u>нова_назва</u>",
+    ),
+    (
+        "slots",
+        "Дати кожному елементу черги свій проміжок часу по черзі, час початку показується в черзі. \
+         Синтаксис: <b>/slots</b> <u>[дата]</u> <u>час</u> <u>хвилини</u>, наприклад <b>/slots</b> 2021-10-12 10:00+03 10. \
+         Без дати проміжки починаються сьогодні, <b>/slots off</b> прибирає їх.",
+    ),
+    (
+        "calendar",
+        "Надіслати проміжки часу черги файлом календаря. Синтаксис: <b>/calendar</b>",
+    ),
+    (
+        "sheet",
+        "Надіслати аркуш запису з вільними місцями, які люди можуть зайняти, лише для адміністраторів. \
+         Синтаксис: <b>/sheet</b> <u>[назва]</u> з часом або темою місця в кожному наступному рядку, \
+         або <b>/sheet</b> <u>кількість</u> <u>[назва]</u> для пронумерованих місць.",
+    ),
+    (
+        "claim",
+        "Зайняти вільне місце в аркуші запису, одне на людину. Синтаксис: <b>/claim</b> <u>місце</u>",
+    ),
+    (
+        "unclaim",
+        "Звільнити своє місце в аркуші запису. Синтаксис: <b>/unclaim</b>",
+    ),
+    (
+        "assign",
+        "Випадково дати кожному в черзі тему, у новій черзі. \
+         Синтаксис: <b>/assign</b> <u>[unique]</u> <u>[seed=N]</u> <u>[назва]</u> <u>[^список]</u> з темами в наступних рядках \
+         або взятими зі списку чи черги з назвою після ^. Якщо тем менше, ніж людей, вони діляться порівну, \
+         <b>unique</b> забороняє повтори, той самий <b>seed</b> дає той самий результат.",
+    ),
+    (
+        "groups",
+        "Розбити чергу на випадкові команди рівного розміру. \
+         Синтаксис: <b>/groups</b> <u>N</u> чи <b>/groups</b> size=<u>K</u>, далі <u>[balance]</u> <u>[save]</u>. \
+         Рядки на кшталт together: Ann, Bob та apart: Carl, Dan після команди тримають людей в одній команді чи в різних. \
+         <b>balance</b> розподіляє людей з однаковою групою в дужках, як-от Ann (KA-01), між командами, \
+         <b>save</b> робить кожну команду чергою.",
+    ),
+    (
+        "pairs",
+        "Розбити людей у черзі на пари так, щоб кожен зустрівся з кожним, перш ніж пари повторяться. \
+         Синтаксис: <b>/pairs</b>, за непарної кількості хтось лишається без пари. \
+         <b>/pairs reset</b> забуває пари чату, лише для адміністраторів.",
+    ),
+    (
+        "santa",
+        "Провести таємного Санту серед людей у черзі, лише для адміністраторів. \
+         Синтаксис: <b>/santa</b> <u>[примітка]</u>, з рядками на кшталт exclude: Ann, Bob після неї для тих, хто не має обирати одне одного. \
+         Кожен отримає свій вибір і примітку в особистому повідомленні, тож має стати в чергу сам \
+         і спершу почати приватний чат з ботом.",
+    ),
+    (
+        "repost",
+        "Надіслати чергу ще раз новим повідомленням. Синтаксис: <b>/repost</b>",
+    ),
+    (
+        "show",
+        "Надіслати чергу, відкриту на сторінці. Синтаксис: <b>/show</b> <u>[сторінка]</u>. \
+         <b>/show all</b> надсилає всю чергу файлом.",
+    ),
+    (
+        "next",
+        "Позначити поточний елемент виконаним і викликати наступний, відсутні пропускаються. Синтаксис: <b>/next</b>",
+    ),
+    (
+        "skip",
+        "Перемістити поточний елемент у кінець або на вказану кількість місць назад і викликати наступний. \
+         Синтаксис: <b>/skip</b> <u>[місця]</u>",
+    ),
+    (
+        "postpone",
+        "Перемістити себе в кінець черги або на вказану кількість місць назад. \
+         Синтаксис: <b>/postpone</b> <u>[місця]</u>",
+    ),
+    (
+        "absent",
+        "Позначити елемент відсутнім: він лишається в черзі, але не викликається. Повторіть, щоб повернути елемент. \
+         Синтаксис: <b>/absent</b> <u>[місце]</u>, без місця позначає вас.",
+    ),
+    (
+        "template",
+        "Змінити вигляд черг чату, лише для адміністраторів. Синтаксис: <b>/template</b> <u>опція</u> <u>значення</u>. \
+         Опції: <b>numbering</b> paren|dot|hash|none, <b>waiting</b>, <b>current</b>, <b>done</b>, <b>skipped</b>, <b>absent</b> <u>[позначка]</u>, \
+         <b>header</b>, <b>footer</b> <u>[текст]</u>, де {name} — назва черги, <b>counts</b> on|off. \
+         <b>/template reset</b> повертає типовий вигляд, <b>/template</b> показує поточний.",
+    ),
+    (
+        "settings",
+        "Показати налаштування чату, адміністратори можуть їх змінити. Синтаксис: <b>/settings</b>",
+    ),
+    (
+        "list",
+        "Показати черги чату, спершу нові. Синтаксис: <b>/list</b>",
+    ),
+    (
+        "archive",
+        "Архівувати чергу, лише для адміністраторів. Її буде збережено, але не можна змінити, і її немає в списку. Синтаксис: <b>/archive</b>",
+    ),
+    (
+        "delete",
+        "Видалити чергу з усіма її елементами, лише для адміністраторів. Синтаксис: <b>/delete</b>",
+    ),
+    (
+        "schedule",
+        "Щотижня створювати перемішану копію черги, лише для адміністраторів. \
+         Синтаксис: <b>/schedule</b> <u>день</u> <u>ГГ:ХХ[+зсув]</u> <u>назва</u> <u>[^список]</u>, \
+         де {n} у назві — номер копії. Час указано в UTC, якщо не вказано зсув. \
+         Люди черги зберігаються як список для розкладу, \
+         зі списком перемішуються його учасники. <b>/schedule</b> показує заплановані черги.",
+    ),
+    (
+        "signup",
+        "Збирати людей у нову чергу кнопкою, поки не мине час. \
+         Синтаксис: <b>/signup</b> <u>[назва]</u> <u>тривалість</u> <u>[shuffle]</u>, тривалість на кшталт 10m, 2h чи 1d. \
+         Черга буде в порядку запису, якщо не вказано shuffle.",
+    ),
+    (
+        "poll",
+        "Надіслати опитування, з учасників якого можна створити черги. \
+         Синтаксис: <b>/poll</b>, а далі питання і варіанти окремими рядками.",
+    ),
+    (
+        "queuefrompoll",
+        "Створити черги з тих, хто проголосував за кожен варіант опитування, надісланого через /poll, у порядку голосування. \
+         Синтаксис: <b>/queuefrompoll</b> <u>[варіант]</u>, де варіант — номер одного варіанта.",
+    ),
+    (
+        "roster",
+        "Зберігати іменовані списки людей, щоб створювати з них черги. \
+         <b>/roster save</b> <u>назва</u> зберігає чергу чи файл, на які відповідає команда, \
+         або учасників, написаних у рядках після назви. \
+         <b>/roster edit</b> <u>назва</u> додає учасників із рядків після назви, рядки з - на початку видаляють їх. \
+         <b>/roster list</b>, <b>/roster show</b> <u>назва</u>, <b>/roster delete</b> <u>назва</u>. Змінювати списки можуть лише адміністратори.",
+    ),
+];
+
+/// A text sent by the bot, `text` renders it in a language.
+pub enum Msg<'a> {
+    NoQueueReply,
+    NonexistentPosition {
+        pos: i32,
+    },
+    ElementGone,
+    OutdatedQueue,
+    ConfirmButton,
+    CancelButton,
+    OnlyAuthor,
+    OnlyAdminsSettings,
+    OnlyAdminsTemplate,
+    NumberingValues,
+    MarkTooLong {
+        max: usize,
+    },
+    TemplateTextTooLong {
+        option: &'a str,
+        max: usize,
+    },
+    CountsValues,
+    UnknownTemplateOption {
+        option: &'a str,
+    },
+    TemplateReset,
+    TemplateUpdated,
+    Inserted {
+        name: &'a str,
+        place: Option<i32>,
+    },
+    Removed {
+        name: &'a str,
+        place: i32,
+    },
+    ReplyToFile,
+    SwapWithItself,
+    OnlyAdminsSwap,
+    Swapped {
+        name1: &'a str,
+        place1: i32,
+        name2: &'a str,
+        place2: i32,
+    },
+    SameName,
+    Renamed {
+        old: Option<&'a str>,
+        new: &'a str,
+    },
+    /// `name` is HTML.
+    Turn {
+        name: &'a str,
+    },
+    QueueOver,
+    PageArgument,
+    PrevPage,
+    NextPage,
+    PageOf {
+        page: i32,
+        pages: usize,
+    },
+    Counts {
+        total: usize,
+        done: usize,
+    },
+    SettingsTitle,
+    ListTitle {
+        total: i64,
+    },
+    NoQueues,
+    UnnamedQueue,
+    ListEntry {
+        count: i64,
+        created: &'a str,
+    },
+    OpenButton {
+        n: i64,
+    },
+    RepostButton {
+        n: i64,
+    },
+    ArchiveButton {
+        n: i64,
+    },
+    OnlyAdminsArchive,
+    Archived,
+    AlreadyArchived,
+    ArchivedQueue,
+    OnlyAdminsDelete,
+    ConfirmDeletion {
+        count: usize,
+    },
+    DeleteButton,
+    Deleted,
+    AutoPinSetting {
+        on: bool,
+    },
+    SilentSetting {
+        on: bool,
+    },
+    ConfirmationsSetting {
+        on: bool,
+    },
+    ShuffleSetting {
+        mode: ShuffleMode,
+    },
+    LanguageSetting {
+        language: Option<Language>,
+    },
+    ExpirySetting {
+        days: Option<i32>,
+    },
+    ExpiryActionSetting {
+        action: ExpiryAction,
+    },
+    QueueExpired {
+        name: Option<&'a str>,
+        days: i32,
+        action: ExpiryAction,
+    },
+    OnlyAdminsSchedule,
+    ScheduleUsage,
+    Scheduled {
+        name: &'a str,
+        next: &'a str,
+    },
+    ScheduleRosterSaved {
+        roster: &'a str,
+    },
+    SchedulesTitle,
+    NoSchedules,
+    ScheduleEntry {
+        name: &'a str,
+        next: &'a str,
+    },
+    UnscheduleButton {
+        n: usize,
+    },
+    Unscheduled,
+    RosterUsage,
+    NoRoster {
+        name: &'a str,
+    },
+    OnlyAdminsRoster,
+    RosterSourceUsage,
+    RosterEditUsage,
+    RosterSaved {
+        name: &'a str,
+        count: usize,
+    },
+    RosterDeleted,
+    RostersTitle,
+    NoRosters,
+    RosterEntry {
+        name: &'a str,
+        count: i64,
+    },
+    SignupUsage,
+    SignupTitle {
+        name: Option<&'a str>,
+    },
+    SignupDeadline {
+        closes: &'a str,
+        shuffle: bool,
+    },
+    SignUpButton,
+    LeaveSignupButton,
+    SignedUp,
+    AlreadySignedUp,
+    LeftSignup,
+    NotSignedUp,
+    SignupIsClosed,
+    SignupClosed {
+        name: Option<&'a str>,
+        count: usize,
+    },
+    NobodySignedUp,
+    PollUsage,
+    ReplyToPoll,
+    AnonymousPoll,
+    PollOptionUsage {
+        count: usize,
+    },
+    NoVoters,
+    PlacesUsage,
+    NobodyCurrent,
+    NotInQueue,
+    MovedBack {
+        name: &'a str,
+        places: Option<i32>,
+    },
+    MarkedAbsent {
+        name: &'a str,
+    },
+    MarkedPresent {
+        name: &'a str,
+    },
+    SwapRequest {
+        to: &'a str,
+        from: &'a str,
+        minutes: i64,
+    },
+    SwapNoOwner {
+        name: &'a str,
+    },
+    AcceptSwapButton,
+    DeclineSwapButton,
+    NotYourSwap,
+    SwapAccepted {
+        name1: &'a str,
+        name2: &'a str,
+    },
+    SwapDeclined,
+    SwapWithdrawn,
+    SwapRequestExpired,
+    SwapRequestGone,
+    SwapElementGone,
+    SlotsUsage,
+    SlotsSet {
+        start: &'a str,
+        minutes: i32,
+    },
+    SlotsRemoved,
+    NoSlots,
+    SheetUsage {
+        max: usize,
+    },
+    OnlyAdminsSheet,
+    NotASheet,
+    SheetSlotsFixed,
+    ReleaseSlotButton,
+    SlotClaimed,
+    SlotTaken,
+    AlreadyClaimed,
+    SlotReleased,
+    NoSlotClaimed,
+    AssignUsage,
+    NoTopics {
+        name: &'a str,
+    },
+    NotEnoughTopics {
+        people: usize,
+        topics: usize,
+    },
+    GroupsUsage,
+    TooManyGroups {
+        max: usize,
+    },
+    UnknownMember {
+        name: &'a str,
+    },
+    GroupsImpossible,
+    GroupsConflict {
+        name1: &'a str,
+        name2: &'a str,
+    },
+    Team {
+        number: usize,
+    },
+    PairsUsage,
+    OnlyAdminsPairs,
+    PairsReset,
+    PairsTitle,
+    Bye {
+        name: &'a str,
+    },
+    SantaUsage,
+    OnlyAdminsSanta,
+    SantaUnlinked {
+        names: &'a str,
+    },
+    SantaNotStarted {
+        names: &'a str,
+    },
+    SantaImpossible,
+    SantaAssignment {
+        chat: &'a str,
+        receiver: &'a str,
+        note: &'a str,
+    },
+    SantaSent {
+        count: usize,
+    },
+    SantaNotDelivered {
+        names: &'a str,
+    },
+}
+
+impl Msg<'_> {
+    pub fn text(&self, lang: Language) -> String {
+        match lang {
+            Language::En => self.en(),
+            Language::Uk => self.uk(),
+        }
+    }
+
+    fn en(&self) -> String {
+        let on_off = |on: &bool| if *on { "on" } else { "off" };
+        match self {
+            Msg::NoQueueReply => {
+                "You must reply to a queue created by this bot for this command to work.".into()
+            }
+            Msg::NonexistentPosition { pos } => format!("Nonexistent position: {}", pos),
+            Msg::ElementGone => "This element is no longer in the queue.".into(),
+            Msg::OutdatedQueue => {
+                "The queue has changed since this message. Run the command anyway?".into()
+            }
+            Msg::ConfirmButton => "Confirm".into(),
+            Msg::CancelButton => "Cancel".into(),
+            Msg::OnlyAuthor => "Only the author of the command can answer this.".into(),
+            Msg::OnlyAdminsSettings => "Only chat admins can change the settings.".into(),
+            Msg::OnlyAdminsTemplate => "Only chat admins can change the template.".into(),
+            Msg::NumberingValues => "Numbering must be one of paren, dot, hash or none.".into(),
+            Msg::MarkTooLong { max } => format!("A mark can't be longer than {} characters.", max),
+            Msg::TemplateTextTooLong { option, max } => {
+                format!("The {} can't be longer than {} characters.", option, max)
+            }
+            Msg::CountsValues => "Counts must be on or off.".into(),
+            Msg::UnknownTemplateOption { option } => {
+                format!("Unknown template option: {}", option)
+            }
+            Msg::TemplateReset => "The template is reset to the defaults.".into(),
+            Msg::TemplateUpdated => {
+                "The template is updated, queues use it from their next change.".into()
+            }
+            Msg::Inserted { name, place } => match place {
+                Some(place) => format!("Inserted {} at {}", name, place),
+                None => format!("Inserted {} at the last position", name),
+            },
+            Msg::Removed { name, place } => format!("Removed {} from {}", name, place),
+            Msg::ReplyToFile => "Please reply to a message with a file.".into(),
+            Msg::SwapWithItself => "Can't swap position with itself".into(),
+            Msg::OnlyAdminsSwap => {
+                "Only chat admins can swap places, ask the owner with /trade instead.".into()
+            }
+            Msg::Swapped {
+                name1,
+                place1,
+                name2,
+                place2,
+            } => format!("Swapped {} ({}) and {} ({})", name1, place1, name2, place2),
+            Msg::SameName => "Can't rename to the same name.".into(),
+            Msg::Renamed { old, new } => {
+                format!("Renamed queue from {} to {}", old.unwrap_or("`empty`"), new)
+            }
+            Msg::Turn { name } => format!("Now it's {}'s turn.", name),
+            Msg::QueueOver => "The queue is over.".into(),
+            Msg::PageArgument => "The page must be a number or \"all\".".into(),
+            Msg::PrevPage => "‹ Prev".into(),
+            Msg::NextPage => "Next ›".into(),
+            Msg::PageOf { page, pages } => format!("page {}/{}", page, pages),
+            Msg::Counts { total, done } => format!("{} in total, {} done", total, done),
+            Msg::SettingsTitle => {
+                "Settings of this chat, admins can press a button to change one.".into()
+            }
+            Msg::ListTitle { total } => format!("Queues of this chat: {}", total),
+            Msg::NoQueues => "There are no queues in this chat.".into(),
+            Msg::UnnamedQueue => "Unnamed queue".into(),
+            Msg::ListEntry { count, created } => {
+                format!("{} in queue · created {}", count, created)
+            }
+            Msg::OpenButton { n } => format!("Open {}", n),
+            Msg::RepostButton { n } => format!("Repost {}", n),
+            Msg::ArchiveButton { n } => format!("Archive {}", n),
+            Msg::OnlyAdminsArchive => "Only chat admins can archive queues.".into(),
+            Msg::Archived => "The queue is archived.".into(),
+            Msg::AlreadyArchived => "The queue is already archived.".into(),
+            Msg::ArchivedQueue => "This queue is archived and can't be changed.".into(),
+            Msg::OnlyAdminsDelete => "Only chat admins can delete queues.".into(),
+            Msg::ConfirmDeletion { count } => format!(
+                "Delete this queue? Its {} elements will be removed for good.",
+                count
+            ),
+            Msg::DeleteButton => "Delete".into(),
+            Msg::Deleted => "The queue is deleted.".into(),
+            Msg::AutoPinSetting { on } => format!("Pin new queues: {}", on_off(on)),
+            Msg::SilentSetting { on } => format!("Silent messages: {}", on_off(on)),
+            Msg::ConfirmationsSetting { on } => format!("Confirmation replies: {}", on_off(on)),
+            Msg::ShuffleSetting { mode } => format!(
+                "Queues from files: {}",
+                match mode {
+                    ShuffleMode::Keep => "keep order",
+                    ShuffleMode::Shuffle => "shuffle",
+                }
+            ),
+            Msg::LanguageSetting { language } => format!(
+                "Language: {}",
+                match language {
+                    Some(Language::En) => "English",
+                    Some(Language::Uk) => "Українська",
+                    None => "of each user",
+                }
+            ),
+            Msg::ExpirySetting { days } => match days {
+                Some(days) => format!("Expire idle queues: after {} days", days),
+                None => "Expire idle queues: never".into(),
+            },
+            Msg::ExpiryActionSetting { action } => format!(
+                "Expired queues: {}",
+                match action {
+                    ExpiryAction::Archive => "archive",
+                    ExpiryAction::Delete => "delete",
+                }
+            ),
+            Msg::QueueExpired { name, days, action } => format!(
+                "The queue{} wasn't changed for {} days and is {}.",
+                name.map(|x| format!(" \"{}\"", x)).unwrap_or_default(),
+                days,
+                match action {
+                    ExpiryAction::Archive => "archived",
+                    ExpiryAction::Delete => "deleted",
+                }
+            ),
+            Msg::OnlyAdminsSchedule => "Only chat admins can schedule queues.".into(),
+            Msg::ScheduleUsage => "Reply to a queue with /schedule day time name, \
+                                   for example /schedule tue 10:00 Lab {n}. \
+                                   The time is in UTC unless it has an offset like 10:00+03."
+                .into(),
+            Msg::Scheduled { name, next } => format!(
+                "A shuffled copy of this queue will be created every week, \
+                 the first one is \"{}\" on {}.",
+                name, next
+            ),
+            Msg::ScheduleRosterSaved { roster } => format!(
+                "The people were saved as the roster \"{}\", change it with /roster edit.",
+                roster
+            ),
+            Msg::SchedulesTitle => "Scheduled queues:".into(),
+            Msg::NoSchedules => {
+                "No queues are scheduled. Reply to a queue with /schedule to add one.".into()
+            }
+            Msg::ScheduleEntry { name, next } => format!("{} · next on {}", name, next),
+            Msg::UnscheduleButton { n } => format!("Cancel {}", n),
+            Msg::Unscheduled => "The schedule is cancelled.".into(),
+            Msg::RosterUsage => "Syntax: /roster list, /roster show name, \
+                                 /roster save name, /roster edit name or /roster delete name."
+                .into(),
+            Msg::NoRoster { name } => format!("There is no roster named \"{}\".", name),
+            Msg::OnlyAdminsRoster => "Only chat admins can change rosters.".into(),
+            Msg::RosterSourceUsage => "Reply to a queue or a file, \
+                                       or write the members on the lines after the name."
+                .into(),
+            Msg::RosterEditUsage => "Write the members to add on the lines after the name, \
+                                     lines starting with - remove members."
+                .into(),
+            Msg::RosterSaved { name, count } => {
+                format!("The roster \"{}\" is saved, members: {}.", name, count)
+            }
+            Msg::RosterDeleted => "The roster is deleted.".into(),
+            Msg::RostersTitle => "Rosters of this chat:".into(),
+            Msg::NoRosters => {
+                "There are no rosters in this chat. Save one with /roster save.".into()
+            }
+            Msg::RosterEntry { name, count } => format!("{} · members: {}", name, count),
+            Msg::SignupUsage => "Syntax: /signup [name] duration [shuffle], \
+                                 for example /signup Lab 2 10m. \
+                                 The duration is in s, m, h or d, up to 7 days."
+                .into(),
+            Msg::SignupTitle { name } => match name {
+                Some(name) => format!("Sign-up for \"{}\"", name),
+                None => "Sign-up for a new queue".into(),
+            },
+            Msg::SignupDeadline { closes, shuffle } => format!(
+                "Open until {}, {}.",
+                closes,
+                if *shuffle {
+                    "then the queue is shuffled"
+                } else {
+                    "the queue keeps the sign-up order"
+                }
+            ),
+            Msg::SignUpButton => "Sign up".into(),
+            Msg::LeaveSignupButton => "Leave".into(),
+            Msg::SignedUp => "You are signed up.".into(),
+            Msg::AlreadySignedUp => "You are already signed up.".into(),
+            Msg::LeftSignup => "You are no longer signed up.".into(),
+            Msg::NotSignedUp => "You aren't signed up.".into(),
+            Msg::SignupIsClosed => "The sign-up is closed.".into(),
+            Msg::SignupClosed { name, count } => format!(
+                "The sign-up{} is closed, {} signed up.",
+                name.map(|x| format!(" for \"{}\"", x)).unwrap_or_default(),
+                count
+            ),
+            Msg::NobodySignedUp => "The sign-up is closed, nobody signed up.".into(),
+            Msg::PollUsage => "Write the question and 2 to 10 options on separate lines \
+                               after /poll."
+                .into(),
+            Msg::ReplyToPoll => "Reply to a poll for this command to work.".into(),
+            Msg::AnonymousPoll => "The votes of an anonymous poll aren't known.".into(),
+            Msg::PollOptionUsage { count } => {
+                format!("Give the number of an option from 1 to {}.", count)
+            }
+            Msg::NoVoters => "Nobody voted yet. Only the votes in polls sent \
+                              with /poll are seen by the bot."
+                .into(),
+            Msg::PlacesUsage => "The number of places must be positive.".into(),
+            Msg::NobodyCurrent => "Nobody is called yet, call someone with /next.".into(),
+            Msg::NotInQueue => "You aren't in this queue.".into(),
+            Msg::MovedBack {
+                name,
+                places: Some(places),
+            } => format!("{} is moved back by {}.", name, places),
+            Msg::MovedBack { name, places: None } => {
+                format!("{} is moved to the end of the queue.", name)
+            }
+            Msg::MarkedAbsent { name } => format!("{} is absent and won't be called.", name),
+            Msg::MarkedPresent { name } => format!("{} is back in the queue.", name),
+            Msg::SwapRequest { to, from, minutes } => format!(
+                "{}, {} asks to trade places with you. The request is open for {} minutes.",
+                to, from, minutes
+            ),
+            Msg::SwapNoOwner { name } => format!(
+                "{} isn't a Telegram user, so nobody can accept the trade. Ask a chat admin to /swap.",
+                name
+            ),
+            Msg::AcceptSwapButton => "Accept".into(),
+            Msg::DeclineSwapButton => "Decline".into(),
+            Msg::NotYourSwap => "This request isn't for you.".into(),
+            Msg::SwapAccepted { name1, name2 } => format!("{} and {} traded places.", name1, name2),
+            Msg::SwapDeclined => "The trade is declined.".into(),
+            Msg::SwapWithdrawn => "The trade request is withdrawn.".into(),
+            Msg::SwapRequestExpired => "The trade request expired.".into(),
+            Msg::SwapRequestGone => "The trade request is no longer valid.".into(),
+            Msg::SwapElementGone => {
+                "One of the places was removed from the queue, so the trade is cancelled.".into()
+            }
+            Msg::SlotsUsage => "Give the start and the minutes per slot, like /slots 10:00+03 10 \
+                                or /slots 2021-10-12 10:00+03 10. /slots off removes the slots."
+                .into(),
+            Msg::SlotsSet { start, minutes } => format!(
+                "The queue starts at {}, {} minutes per element.",
+                start, minutes
+            ),
+            Msg::SlotsRemoved => "The queue has no time slots anymore.".into(),
+            Msg::NoSlots => "The queue has no time slots, set them with /slots.".into(),
+            Msg::SheetUsage { max } => format!(
+                "Write the name of the sheet and then a slot on every next line, \
+                 or /sheet 10 name for numbered slots. A sheet has 1 to {} slots.",
+                max
+            ),
+            Msg::OnlyAdminsSheet => "Only chat admins can post sheets.".into(),
+            Msg::NotASheet => {
+                "This queue isn't a sheet, reply to a sheet posted with /sheet.".into()
+            }
+            Msg::SheetSlotsFixed => {
+                "The slots of a sheet stay in place, use /claim and /unclaim instead.".into()
+            }
+            Msg::ReleaseSlotButton => "Release my slot".into(),
+            Msg::SlotClaimed => "The slot is yours.".into(),
+            Msg::SlotTaken => "This slot is already taken.".into(),
+            Msg::AlreadyClaimed => "You already have a slot, release it first.".into(),
+            Msg::SlotReleased => "Your slot is free again.".into(),
+            Msg::NoSlotClaimed => "You have no slot in this sheet.".into(),
+            Msg::AssignUsage => {
+                "Reply to a queue of people and write the topics on the next lines, \
+                                 or take them from a list with ^name. Options: unique, seed=N."
+                    .into()
+            }
+            Msg::NoTopics { name } => format!("There is no roster or queue named \"{}\".", name),
+            Msg::NotEnoughTopics { people, topics } => format!(
+                "There are {} people but only {} unique topics.",
+                people, topics
+            ),
+            Msg::GroupsUsage => "Reply to a queue of people with /groups N or /groups size=K, \
+                                 options: balance, save. Constraints go on the next lines, \
+                                 like together: Ann, Bob or apart: Carl, Dan."
+                .into(),
+            Msg::TooManyGroups { max } => format!("At most {} teams can be saved.", max),
+            Msg::UnknownMember { name } => format!("There is no {} in the queue.", name),
+            Msg::GroupsImpossible => {
+                "Couldn't keep everyone apart as asked, try more teams.".into()
+            }
+            Msg::GroupsConflict { name1, name2 } => format!(
+                "{} and {} can't be both together and apart, fix the constraints.",
+                name1, name2
+            ),
+            Msg::Team { number } => format!("Team {}", number),
+            Msg::PairsUsage => "Reply to a queue of at least two people with /pairs, \
+                                or forget who met with /pairs reset."
+                .into(),
+            Msg::OnlyAdminsPairs => "Only chat admins can reset the pairs.".into(),
+            Msg::PairsReset => "The pairs are forgotten, everyone can meet again.".into(),
+            Msg::PairsTitle => "Pairs".into(),
+            Msg::Bye { name } => format!("{} has a bye this time.", name),
+            Msg::SantaUsage => "Reply to a queue of at least two people with /santa [note], \
+                                exclusions go on the next lines like exclude: Ann, Bob."
+                .into(),
+            Msg::OnlyAdminsSanta => "Only chat admins can draw a Secret Santa.".into(),
+            Msg::SantaUnlinked { names } => format!(
+                "Everyone has to join the queue themselves to get a private message, \
+                 these didn't: {}.",
+                names
+            ),
+            Msg::SantaNotStarted { names } => format!(
+                "These people have to start a private chat with the bot first: {}.",
+                names
+            ),
+            Msg::SantaImpossible => {
+                "Nobody can be drawn for somebody with these exclusions.".into()
+            }
+            Msg::SantaAssignment {
+                chat,
+                receiver,
+                note,
+            } => {
+                let mut text = format!(
+                    "Secret Santa in \"{}\": you give a present to {}.",
+                    chat, receiver
+                );
+                if !note.is_empty() {
+                    text.push_str(&format!("\n{}", note));
+                }
+                text
+            }
+            Msg::SantaSent { count } => format!(
+                "The draw is done, {} people got their pick in a private message.",
+                count
+            ),
+            Msg::SantaNotDelivered { names } => format!(
+                "Couldn't write to {}, they have to start the bot and the draw has to be redone.",
+                names
+            ),
+        }
+    }
+
+    fn uk(&self) -> String {
+        let on_off = |on: &bool| if *on { "увімк." } else { "вимк." };
+        match self {
+            Msg::NoQueueReply => {
+                "Щоб ця команда спрацювала, дайте відповідь на чергу, створену цим ботом.".into()
+            }
+            Msg::NonexistentPosition { pos } => format!("Немає такого місця: {}", pos),
+            Msg::ElementGone => "Цього елемента вже немає в черзі.".into(),
+            Msg::OutdatedQueue => {
+                "Черга змінилася після цього повідомлення. Все одно виконати команду?".into()
+            }
+            Msg::ConfirmButton => "Підтвердити".into(),
+            Msg::CancelButton => "Скасувати".into(),
+            Msg::OnlyAuthor => "Відповісти може лише автор команди.".into(),
+            Msg::OnlyAdminsSettings => {
+                "Лише адміністратори чату можуть змінювати налаштування.".into()
+            }
+            Msg::OnlyAdminsTemplate => "Лише адміністратори чату можуть змінювати шаблон.".into(),
+            Msg::NumberingValues => "Нумерація має бути одним із: paren, dot, hash, none.".into(),
+            Msg::MarkTooLong { max } => {
+                format!("Позначка не може бути довшою за {} символів.", max)
+            }
+            Msg::TemplateTextTooLong { option, max } => {
+                format!("{} не може бути довшим за {} символів.", option, max)
+            }
+            Msg::CountsValues => "Лічильники мають бути on або off.".into(),
+            Msg::UnknownTemplateOption { option } => {
+                format!("Невідома опція шаблону: {}", option)
+            }
+            Msg::TemplateReset => "Шаблон повернуто до типового.".into(),
+            Msg::TemplateUpdated => {
+                "Шаблон оновлено, черги використають його після наступної зміни.".into()
+            }
+            Msg::Inserted { name, place } => match place {
+                Some(place) => format!("{} додано на місце {}", name, place),
+                None => format!("{} додано в кінець черги", name),
+            },
+            Msg::Removed { name, place } => format!("{} видалено з місця {}", name, place),
+            Msg::ReplyToFile => "Будь ласка, дайте відповідь на повідомлення з файлом.".into(),
+            Msg::SwapWithItself => "Не можна поміняти місце саме з собою".into(),
+            Msg::OnlyAdminsSwap => {
+                "Лише адміністратори чату можуть міняти місця, попросіть власника через /trade."
+                    .into()
+            }
+            Msg::Swapped {
+                name1,
+                place1,
+                name2,
+                place2,
+            } => format!(
+                "{} ({}) і {} ({}) помінялися місцями",
+                name1, place1, name2, place2
+            ),
+            Msg::SameName => "Нова назва збігається зі старою.".into(),
+            Msg::Renamed { old, new } => format!(
+                "Чергу перейменовано з {} на {}",
+                old.unwrap_or("`без назви`"),
+                new
+            ),
+            Msg::Turn { name } => format!("Зараз черга {}.", name),
+            Msg::QueueOver => "Черга закінчилася.".into(),
+            Msg::PageArgument => "Сторінка має бути числом або \"all\".".into(),
+            Msg::PrevPage => "‹ Назад".into(),
+            Msg::NextPage => "Далі ›".into(),
+            Msg::PageOf { page, pages } => format!("сторінка {}/{}", page, pages),
+            Msg::Counts { total, done } => format!("усього {}, виконано {}", total, done),
+            Msg::SettingsTitle => {
+                "Налаштування цього чату, адміністратори можуть змінити їх кнопками.".into()
+            }
+            Msg::ListTitle { total } => format!("Черги цього чату: {}", total),
+            Msg::NoQueues => "У цьому чаті немає черг.".into(),
+            Msg::UnnamedQueue => "Черга без назви".into(),
+            Msg::ListEntry { count, created } => {
+                format!("у черзі {} · створена {}", count, created)
+            }
+            Msg::OpenButton { n } => format!("Відкрити {}", n),
+            Msg::RepostButton { n } => format!("Надіслати {}", n),
+            Msg::ArchiveButton { n } => format!("Архівувати {}", n),
+            Msg::OnlyAdminsArchive => "Лише адміністратори чату можуть архівувати черги.".into(),
+            Msg::Archived => "Чергу архівовано.".into(),
+            Msg::AlreadyArchived => "Черга вже в архіві.".into(),
+            Msg::ArchivedQueue => "Ця черга в архіві, її не можна змінити.".into(),
+            Msg::OnlyAdminsDelete => "Лише адміністратори чату можуть видаляти черги.".into(),
+            Msg::ConfirmDeletion { count } => format!(
+                "Видалити цю чергу? Усі її елементи ({}) буде видалено назавжди.",
+                count
+            ),
+            Msg::DeleteButton => "Видалити".into(),
+            Msg::Deleted => "Чергу видалено.".into(),
+            Msg::AutoPinSetting { on } => format!("Закріплювати нові черги: {}", on_off(on)),
+            Msg::SilentSetting { on } => format!("Тихі повідомлення: {}", on_off(on)),
+            Msg::ConfirmationsSetting { on } => {
+                format!("Відповіді про зміни: {}", on_off(on))
+            }
+            Msg::ShuffleSetting { mode } => format!(
+                "Черги з файлів: {}",
+                match mode {
+                    ShuffleMode::Keep => "за порядком",
+                    ShuffleMode::Shuffle => "перемішувати",
+                }
+            ),
+            Msg::LanguageSetting { language } => format!(
+                "Мова: {}",
+                match language {
+                    Some(Language::En) => "English",
+                    Some(Language::Uk) => "Українська",
+                    None => "кожного користувача",
+                }
+            ),
+            Msg::ExpirySetting { days } => match days {
+                Some(days) => format!("Прибирати неактивні черги: через {} дн.", days),
+                None => "Прибирати неактивні черги: ніколи".into(),
+            },
+            Msg::ExpiryActionSetting { action } => format!(
+                "Неактивні черги: {}",
+                match action {
+                    ExpiryAction::Archive => "архівувати",
+                    ExpiryAction::Delete => "видаляти",
+                }
+            ),
+            Msg::QueueExpired { name, days, action } => format!(
+                "Черга{} не змінювалася {} дн., тому її {}.",
+                name.map(|x| format!(" «{}»", x)).unwrap_or_default(),
+                days,
+                match action {
+                    ExpiryAction::Archive => "архівовано",
+                    ExpiryAction::Delete => "видалено",
+                }
+            ),
+            Msg::OnlyAdminsSchedule => "Лише адміністратори чату можуть планувати черги.".into(),
+            Msg::ScheduleUsage => "Дайте відповідь на чергу командою /schedule день час назва, \
+                                   наприклад /schedule tue 10:00 Лаба {n}. \
+                                   Час указано в UTC, якщо немає зсуву на кшталт 10:00+03."
+                .into(),
+            Msg::Scheduled { name, next } => format!(
+                "Щотижня буде створено перемішану копію цієї черги, \
+                 перша — «{}» {}.",
+                name, next
+            ),
+            Msg::ScheduleRosterSaved { roster } => format!(
+                "Людей збережено як список «{}», змінити його можна через /roster edit.",
+                roster
+            ),
+            Msg::SchedulesTitle => "Заплановані черги:".into(),
+            Msg::NoSchedules => {
+                "Немає запланованих черг. Дайте відповідь на чергу командою /schedule, щоб додати."
+                    .into()
+            }
+            Msg::ScheduleEntry { name, next } => format!("{} · наступна {}", name, next),
+            Msg::UnscheduleButton { n } => format!("Скасувати {}", n),
+            Msg::Unscheduled => "Розклад скасовано.".into(),
+            Msg::RosterUsage => "Синтаксис: /roster list, /roster show назва, \
+                                 /roster save назва, /roster edit назва або /roster delete назва."
+                .into(),
+            Msg::NoRoster { name } => format!("Немає списку «{}».", name),
+            Msg::OnlyAdminsRoster => "Лише адміністратори чату можуть змінювати списки.".into(),
+            Msg::RosterSourceUsage => "Дайте відповідь на чергу чи файл \
+                                       або напишіть учасників у рядках після назви."
+                .into(),
+            Msg::RosterEditUsage => "Напишіть учасників, яких треба додати, у рядках після назви, \
+                                     рядки з - на початку видаляють учасників."
+                .into(),
+            Msg::RosterSaved { name, count } => {
+                format!("Список «{}» збережено, учасників: {}.", name, count)
+            }
+            Msg::RosterDeleted => "Список видалено.".into(),
+            Msg::RostersTitle => "Списки цього чату:".into(),
+            Msg::NoRosters => {
+                "У цьому чаті немає списків. Збережіть список командою /roster save.".into()
+            }
+            Msg::RosterEntry { name, count } => format!("{} · учасників: {}", name, count),
+            Msg::SignupUsage => "Синтаксис: /signup [назва] тривалість [shuffle], \
+                                 наприклад /signup Лаба 2 10m. \
+                                 Тривалість у s, m, h або d, до 7 днів."
+                .into(),
+            Msg::SignupTitle { name } => match name {
+                Some(name) => format!("Запис у чергу «{}»", name),
+                None => "Запис у нову чергу".into(),
+            },
+            Msg::SignupDeadline { closes, shuffle } => format!(
+                "Відкрито до {}, {}.",
+                closes,
+                if *shuffle {
+                    "потім чергу буде перемішано"
+                } else {
+                    "черга буде в порядку запису"
+                }
+            ),
+            Msg::SignUpButton => "Записатися".into(),
+            Msg::LeaveSignupButton => "Вийти".into(),
+            Msg::SignedUp => "Вас записано.".into(),
+            Msg::AlreadySignedUp => "Ви вже записані.".into(),
+            Msg::LeftSignup => "Вас більше немає в записі.".into(),
+            Msg::NotSignedUp => "Ви не записані.".into(),
+            Msg::SignupIsClosed => "Запис закрито.".into(),
+            Msg::SignupClosed { name, count } => format!(
+                "Запис{} закрито, записалося: {}.",
+                name.map(|x| format!(" у чергу «{}»", x))
+                    .unwrap_or_default(),
+                count
+            ),
+            Msg::NobodySignedUp => "Запис закрито, ніхто не записався.".into(),
+            Msg::PollUsage => "Напишіть питання і від 2 до 10 варіантів окремими рядками \
+                               після /poll."
+                .into(),
+            Msg::ReplyToPoll => "Щоб ця команда спрацювала, дайте відповідь на опитування.".into(),
+            Msg::AnonymousPoll => "Голоси анонімного опитування невідомі.".into(),
+            Msg::PollOptionUsage { count } => {
+                format!("Вкажіть номер варіанта від 1 до {}.", count)
+            }
+            Msg::NoVoters => "Ще ніхто не проголосував. Бот бачить лише голоси \
+                              в опитуваннях, надісланих через /poll."
+                .into(),
+            Msg::PlacesUsage => "Кількість місць має бути додатною.".into(),
+            Msg::NobodyCurrent => "Ще нікого не викликано, викличте когось через /next.".into(),
+            Msg::NotInQueue => "Вас немає в цій черзі.".into(),
+            Msg::MovedBack {
+                name,
+                places: Some(places),
+            } => format!("{} переміщено назад на кількість місць: {}.", name, places),
+            Msg::MovedBack { name, places: None } => {
+                format!("{} переміщено в кінець черги.", name)
+            }
+            Msg::MarkedAbsent { name } => format!("{} відсутні й не будуть викликані.", name),
+            Msg::MarkedPresent { name } => format!("{} знову в черзі.", name),
+            Msg::SwapRequest { to, from, minutes } => format!(
+                "{}, {} просить помінятися з вами місцями. Запит діє {} хв.",
+                to, from, minutes
+            ),
+            Msg::SwapNoOwner { name } => format!(
+                "{} не є користувачем Telegram, тож ніхто не прийме обмін. Попросіть адміністратора чату про /swap.",
+                name
+            ),
+            Msg::AcceptSwapButton => "Прийняти".into(),
+            Msg::DeclineSwapButton => "Відхилити".into(),
+            Msg::NotYourSwap => "Цей запит не для вас.".into(),
+            Msg::SwapAccepted { name1, name2 } => {
+                format!("{} і {} помінялися місцями.", name1, name2)
+            }
+            Msg::SwapDeclined => "Обмін відхилено.".into(),
+            Msg::SwapWithdrawn => "Запит на обмін скасовано.".into(),
+            Msg::SwapRequestExpired => "Час запиту на обмін минув.".into(),
+            Msg::SwapRequestGone => "Запит на обмін уже недійсний.".into(),
+            Msg::SwapElementGone => {
+                "Одне з місць прибрали з черги, тож обмін скасовано.".into()
+            }
+            Msg::SlotsUsage => {
+                "Вкажіть початок і кількість хвилин на елемент, наприклад /slots 10:00+03 10 \
+                                або /slots 2021-10-12 10:00+03 10. /slots off прибирає проміжки."
+                    .into()
+            }
+            Msg::SlotsSet { start, minutes } => {
+                format!("Черга починається о {}, {} хв на елемент.", start, minutes)
+            }
+            Msg::SlotsRemoved => "Черга більше не має проміжків часу.".into(),
+            Msg::NoSlots => "Черга не має проміжків часу, задайте їх через /slots.".into(),
+            Msg::SheetUsage { max } => format!(
+                "Напишіть назву аркуша, а потім по місцю в кожному наступному рядку, \
+                 або /sheet 10 назва для пронумерованих місць. В аркуші від 1 до {} місць.",
+                max
+            ),
+            Msg::OnlyAdminsSheet => {
+                "Лише адміністратори чату можуть надсилати аркуші запису.".into()
+            }
+            Msg::NotASheet => {
+                "Ця черга не є аркушем запису, дайте відповідь на аркуш, надісланий через /sheet."
+                    .into()
+            }
+            Msg::SheetSlotsFixed => {
+                "Місця аркуша запису не рухаються, скористайтеся /claim та /unclaim.".into()
+            }
+            Msg::ReleaseSlotButton => "Звільнити моє місце".into(),
+            Msg::SlotClaimed => "Місце ваше.".into(),
+            Msg::SlotTaken => "Це місце вже зайняте.".into(),
+            Msg::AlreadyClaimed => "У вас уже є місце, спершу звільніть його.".into(),
+            Msg::SlotReleased => "Ваше місце знову вільне.".into(),
+            Msg::NoSlotClaimed => "У вас немає місця в цьому аркуші.".into(),
+            Msg::AssignUsage => {
+                "Дайте відповідь на чергу людей і напишіть теми в наступних рядках \
+                                 або візьміть їх зі списку через ^назва. Опції: unique, seed=N."
+                    .into()
+            }
+            Msg::NoTopics { name } => format!("Немає списку чи черги з назвою «{}».", name),
+            Msg::NotEnoughTopics { people, topics } => {
+                format!("Людей: {}, а різних тем лише {}.", people, topics)
+            }
+            Msg::GroupsUsage => {
+                "Дайте відповідь на чергу людей командою /groups N чи /groups size=K, \
+                                 опції: balance, save. Обмеження пишіть у наступних рядках, \
+                                 як-от together: Ann, Bob чи apart: Carl, Dan."
+                    .into()
+            }
+            Msg::TooManyGroups { max } => format!("Зберегти можна щонайбільше {} команд.", max),
+            Msg::UnknownMember { name } => format!("У черзі немає {}.", name),
+            Msg::GroupsImpossible => {
+                "Не вдалося розвести всіх, як просили, спробуйте більше команд.".into()
+            }
+            Msg::GroupsConflict { name1, name2 } => format!(
+                "{} та {} не можуть бути водночас разом і окремо, виправте умови.",
+                name1, name2
+            ),
+            Msg::Team { number } => format!("Команда {}", number),
+            Msg::PairsUsage => "Дайте відповідь командою /pairs на чергу щонайменше з двох людей \
+                                або забудьте, хто з ким зустрічався, через /pairs reset."
+                .into(),
+            Msg::OnlyAdminsPairs => "Лише адміністратори чату можуть скидати пари.".into(),
+            Msg::PairsReset => "Пари забуто, всі можуть зустрітися знову.".into(),
+            Msg::PairsTitle => "Пари".into(),
+            Msg::Bye { name } => format!("{} цього разу без пари.", name),
+            Msg::SantaUsage => {
+                "Дайте відповідь на чергу щонайменше з двох людей командою /santa [примітка], \
+                                винятки пишіть у наступних рядках, як-от exclude: Ann, Bob."
+                    .into()
+            }
+            Msg::OnlyAdminsSanta => {
+                "Лише адміністратори чату можуть проводити таємного Санту.".into()
+            }
+            Msg::SantaUnlinked { names } => format!(
+                "Щоб отримати особисте повідомлення, кожен має стати в чергу сам, \
+                 а ці люди — ні: {}.",
+                names
+            ),
+            Msg::SantaNotStarted { names } => format!(
+                "Ці люди мають спершу почати приватний чат з ботом: {}.",
+                names
+            ),
+            Msg::SantaImpossible => "З такими винятками не вийде нікому нікого обрати.".into(),
+            Msg::SantaAssignment {
+                chat,
+                receiver,
+                note,
+            } => {
+                let mut text = format!(
+                    "Таємний Санта в «{}»: ви даруєте подарунок для {}.",
+                    chat, receiver
+                );
+                if !note.is_empty() {
+                    text.push_str(&format!("\n{}", note));
+                }
+                text
+            }
+            Msg::SantaSent { count } => format!(
+                "Жеребкування проведено, {} людей отримали свій вибір в особистих повідомленнях.",
+                count
+            ),
+            Msg::SantaNotDelivered { names } => format!(
+                "Не вдалося написати {}, їм треба почати бота, а жеребкування — повторити.",
+                names
+            ),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::{da, error};
+
+/// Connections kept for advisory locks once released, more are opened when needed.
+const IDLE_CONNECTIONS: usize = 4;
+
+/// Serializes modifications of the same queue inside this process and, through a Postgres
+/// advisory lock, across processes, so the stored queue and its rendered messages change
+/// in the same order.
+#[derive(Clone, Default)]
+pub struct QueueLocks {
+    locks: Arc<Mutex<HashMap<i64, Arc<AsyncMutex<()>>>>>,
+    /// Connections which hold no advisory lock.
+    idle: Arc<Mutex<Vec<da::QueueRepository>>>,
+}
+
+impl QueueLocks {
+    pub async fn lock(&self, queue_id: i64) -> error::Result<QueueGuard> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(queue_id)
+            .or_default()
+            .clone();
+
+        let mut guard = QueueGuard {
+            guard: Some(lock.lock_owned().await),
+            db: None,
+            queue_id,
+            locks: self.clone(),
+        };
+        // other processes are waited for on a connection of the guard's own,
+        // which blocks, so not on the async workers
+        let idle = self.idle.clone();
+        let db = tokio::task::spawn_blocking(move || -> error::Result<_> {
+            let repo = idle.lock().unwrap().pop().unwrap_or_else(|| {
+                da::QueueRepository::from_connection(crate::establish_connection())
+            });
+            repo.lock_queue(&da::QueueKey { id: queue_id })?;
+            Ok(repo)
+        })
+        .await??;
+        guard.db = Some(db);
+        Ok(guard)
+    }
+
+    /// Unlocks the queue for other processes, then for this one.
+    fn release(
+        &self,
+        queue_id: i64,
+        guard: Option<OwnedMutexGuard<()>>,
+        db: Option<da::QueueRepository>,
+    ) {
+        if let Some(db) = db {
+            // closing the connection would release the lock too, but only eventually
+            match db.unlock_queue(&da::QueueKey { id: queue_id }) {
+                Ok(()) => {
+                    let mut idle = self.idle.lock().unwrap();
+                    if idle.len() < IDLE_CONNECTIONS {
+                        idle.push(db);
+                    }
+                }
+                Err(e) => log::error!("Couldn't unlock queue {}: {}", queue_id, e),
+            }
+        }
+
+        let mut locks = self.locks.lock().unwrap();
+        drop(guard);
+        // nobody else holds or waits for the lock, so it can be forgotten
+        if let Some(lock) = locks.get(&queue_id) {
+            if Arc::strong_count(lock) == 1 {
+                locks.remove(&queue_id);
+            }
+        }
+    }
+}
+
+pub struct QueueGuard {
+    guard: Option<OwnedMutexGuard<()>>,
+    /// Holds the advisory lock of the queue.
+    db: Option<da::QueueRepository>,
+    queue_id: i64,
+    locks: QueueLocks,
+}
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        let (locks, queue_id) = (self.locks.clone(), self.queue_id);
+        let (guard, db) = (self.guard.take(), self.db.take());
+        // unlocking blocks as well
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || locks.release(queue_id, guard, db));
+            }
+            Err(_) => locks.release(queue_id, guard, db),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "needs DATABASE_URL"]
+    async fn guard_releases_the_advisory_lock() {
+        // separate sets of locks act like separate processes
+        let (first, second) = (QueueLocks::default(), QueueLocks::default());
+        let queue_id = i64::MIN + 29;
+
+        let guard = first.lock(queue_id).await.unwrap();
+        let waiting = tokio::time::timeout(Duration::from_millis(300), second.lock(queue_id)).await;
+        assert!(waiting.is_err());
+
+        drop(guard);
+        let relocked = tokio::time::timeout(Duration::from_secs(5), second.lock(queue_id)).await;
+        assert!(relocked.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "needs DATABASE_URL"]
+    async fn released_connections_are_reused() {
+        let locks = QueueLocks::default();
+        let queue_id = i64::MIN + 30;
+
+        for _ in 0..3 {
+            drop(locks.lock(queue_id).await.unwrap());
+            // the guard is released on a blocking thread
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert_eq!(locks.idle.lock().unwrap().len(), 1);
+        }
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+}
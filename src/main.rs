@@ -1,21 +1,53 @@
+mod assign;
+mod callback;
 mod consts;
 mod da;
 mod error;
+mod expiry;
+mod groups;
+mod i18n;
+mod locks;
+mod pairs;
+mod render;
+mod santa;
+mod schedule;
+mod settings;
+mod signup;
+mod slots;
+mod trade;
 
 #[macro_use]
 extern crate diesel;
 
-
+use chrono::Utc;
 use diesel::{Connection, PgConnection};
 use futures::Future;
-use std::{collections::HashMap, env, net::Ipv4Addr, str::from_utf8};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    net::Ipv4Addr,
+    str::from_utf8,
+};
 use teloxide::{
     net::Download,
-    payloads::SendMessageSetters,
+    payloads::{
+        EditMessageTextSetters, PinChatMessageSetters, SendDocumentSetters, SendMessageSetters,
+        SendPollSetters, UnpinChatMessageSetters,
+    },
     prelude::*,
-    types::File,
-    utils::command::{BotCommand, ParseError},
+    types::{
+        Chat, ChatAction, File, InlineKeyboardButton, InlineKeyboardMarkup, InputFile, Message,
+        ParseMode, PollAnswer, PollType,
+    },
+    utils::{
+        command::{BotCommand, ParseError},
+        html,
+    },
+    ApiError, RequestError,
 };
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use i18n::Msg;
 use warp::{http::Response, Filter};
 
 #[tokio::main]
@@ -24,44 +56,74 @@ async fn main() {
 }
 
 #[derive(BotCommand, Debug)]
-#[command(rename = "lowercase", description = "These commands are supported:")]
+#[command(rename = "lowercase")]
 enum QueueCommand {
-    #[command(description = "Obtain help.")]
     Help,
-    #[command(
-        description = "Swap positions in the queue. Syntax: <b>/swap</b> <u>place</u> <u>place</u>",
-        parse_with = "split"
-    )]
+    Start,
+    #[command(parse_with = "split")]
     Swap(i32, i32),
-    #[command(
-        rename = "queuerand",
-        description = "Create a new queue from another queue with shuffling. Syntax: <b>/queuerand</b> <u>[qname]</u>.",
-        parse_with = "accept_string_opt"
-    )]
-    RandomQueue(Option<String>),
-    #[command(
-        rename = "queuefile",
-        description = "Create a new queue from a file without shuffling. Syntax: <b>/queuefile</b> <u>[qname]</u>.",
-        parse_with = "accept_string_opt"
-    )]
-    CreateQueueFromFile(Option<String>),
-    #[command(
-        rename = "insert",
-        description = "Add an element to a queue. Syntax: <b>/insert</b> <u>name</u> <u>[^place]</u>. \
-                       If place isn't provided then inserts to the end of a queue.",
-        parse_with = "accept_string_and_number"
-    )]
+    #[command(rename = "trade")]
+    Trade(i32),
+    #[command(rename = "queuerand", parse_with = "accept_name_and_roster")]
+    RandomQueue(Option<String>, Option<String>),
+    #[command(rename = "queuefile", parse_with = "accept_name_and_roster")]
+    CreateQueueFromFile(Option<String>, Option<String>),
+    #[command(rename = "insert", parse_with = "accept_string_and_number")]
     Insert(String, Option<i32>),
-    #[command(
-        rename = "remove",
-        description = "Remove an element from a queue. Syntax: <b>/remove</b> <u>place</u>"
-    )]
+    #[command(rename = "remove")]
     Remove(i32),
-    #[command(
-        rename = "qname",
-        description = "Set a new name for the queue. Syntax: <b>/qname</b> <u>new_name</u>"
-    )]
+    #[command(rename = "qname")]
     Queuename(String),
+    #[command(rename = "slots", parse_with = "accept_string_opt")]
+    Slots(Option<String>),
+    #[command(rename = "calendar")]
+    Calendar,
+    #[command(rename = "sheet", parse_with = "accept_string_opt")]
+    Sheet(Option<String>),
+    #[command(rename = "claim")]
+    Claim(i32),
+    #[command(rename = "unclaim")]
+    Unclaim,
+    #[command(rename = "assign", parse_with = "accept_string_opt")]
+    Assign(Option<String>),
+    #[command(rename = "groups", parse_with = "accept_string_opt")]
+    Groups(Option<String>),
+    #[command(rename = "pairs", parse_with = "accept_string_opt")]
+    Pairs(Option<String>),
+    #[command(rename = "santa", parse_with = "accept_string_opt")]
+    Santa(Option<String>),
+    #[command(rename = "repost")]
+    Repost,
+    #[command(rename = "show", parse_with = "accept_string_opt")]
+    Show(Option<String>),
+    #[command(rename = "next")]
+    Next,
+    #[command(rename = "skip", parse_with = "accept_number_opt")]
+    Skip(Option<i32>),
+    #[command(rename = "postpone", parse_with = "accept_number_opt")]
+    Postpone(Option<i32>),
+    #[command(rename = "absent", parse_with = "accept_number_opt")]
+    Absent(Option<i32>),
+    #[command(rename = "template", parse_with = "accept_string_opt")]
+    Template(Option<String>),
+    #[command(rename = "settings")]
+    Settings,
+    #[command(rename = "list")]
+    List,
+    #[command(rename = "archive")]
+    Archive,
+    #[command(rename = "delete")]
+    Delete,
+    #[command(rename = "schedule", parse_with = "accept_string_opt")]
+    Schedule(Option<String>),
+    #[command(rename = "roster", parse_with = "accept_string_opt")]
+    Roster(Option<String>),
+    #[command(rename = "signup", parse_with = "accept_string_opt")]
+    Signup(Option<String>),
+    #[command(rename = "poll", parse_with = "accept_string_opt")]
+    Poll(Option<String>),
+    #[command(rename = "queuefrompoll", parse_with = "accept_string_opt")]
+    QueueFromPoll(Option<String>),
 }
 
 fn accept_string_and_number(input: String) -> Result<(String, Option<i32>), ParseError> {
@@ -79,6 +141,21 @@ fn accept_string_and_number(input: String) -> Result<(String, Option<i32>), Pars
     }
 }
 
+/// Parses `[name] [^roster]`, where either part can be left out.
+fn accept_name_and_roster(input: String) -> Result<(Option<String>, Option<String>), ParseError> {
+    let (name, roster) = split_roster(&input);
+    let name = Some(name).filter(|x| !x.is_empty()).map(str::to_string);
+    Ok((name, roster.map(str::to_string)))
+}
+
+/// Splits `text ^roster` into the text and the roster name.
+fn split_roster(input: &str) -> (&str, Option<&str>) {
+    match input.split_once('^') {
+        Some((text, roster)) => (text.trim(), Some(roster.trim())),
+        None => (input.trim(), None),
+    }
+}
+
 fn accept_string_opt(input: String) -> Result<(Option<String>,), ParseError> {
     let trim = input.trim();
     Ok(if trim.is_empty() {
@@ -93,6 +170,18 @@ fn accept_string_opt(input: String) -> Result<(Option<String>,), ParseError> {
     })
 }
 
+fn accept_number_opt(input: String) -> Result<(Option<i32>,), ParseError> {
+    let trim = input.trim();
+    if trim.is_empty() {
+        Ok((None,))
+    } else {
+        let number = trim
+            .parse::<i32>()
+            .map_err(|e| ParseError::Custom(e.into()))?;
+        Ok((Some(number),))
+    }
+}
+
 async fn run() {
     teloxide::enable_logging!();
 
@@ -104,53 +193,160 @@ async fn run() {
         .expect("Migrations should be run successfully.");
 
     log::info!("Starting the bot...");
-    let repl = create_bot();
+    let bot = Bot::from_env();
+    let locks = locks::QueueLocks::default();
+    let repl = create_bot(bot.clone(), locks.clone());
+    let expire = expiry::run(bot.clone(), locks);
+    let create_scheduled = schedule::run(bot.clone());
+    let close_signups = signup::run(bot.clone());
+    let expire_trades = trade::run(bot);
     let serve = create_http_server();
 
-    tokio::join!(repl, serve);
+    tokio::join!(
+        repl,
+        expire,
+        create_scheduled,
+        close_signups,
+        expire_trades,
+        serve
+    );
+}
+
+/// A command re-run from a confirmation button,
+/// pinned to the queue and its version the user agreed to.
+#[derive(Clone, Copy, Debug)]
+struct Confirmed {
+    queue_id: i64,
+    version: i32,
 }
 
-async fn answer(cx: UpdateWithCx<Bot, Message>, command: QueueCommand) -> error::Result<()> {
+async fn answer(
+    cx: UpdateWithCx<Bot, Message>,
+    command: QueueCommand,
+    locks: locks::QueueLocks,
+    confirmed: Option<Confirmed>,
+) -> error::Result<()> {
     let conn = establish_connection();
     let repo = da::QueueRepository::from_connection(conn);
     let chat_id = cx.update.chat_id();
     let chat = repo.get_or_create_chat(chat_id)?;
+    let settings = repo.get_settings(chat_id)?;
+    let silent = settings.silent;
+    let lang = i18n::resolve(
+        settings.language,
+        cx.update
+            .from()
+            .and_then(|user| user.language_code.as_deref()),
+    );
     let command_handler = CommandHandler {
         repo,
         cx: &cx,
         chat,
+        settings,
+        lang,
+        locks,
+        confirmed,
     };
 
     log::info!("Chat: {}; Command: {:?}", chat_id, command);
 
     let res = match command {
-        QueueCommand::Help => {
-            cx.answer(QueueCommand::descriptions())
-                .parse_mode(teloxide::types::ParseMode::Html)
+        // starting the bot in private creates the chat, so it can write to the user
+        QueueCommand::Help | QueueCommand::Start => {
+            cx.answer(i18n::help(lang))
+                .parse_mode(ParseMode::Html)
                 .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
                 .send()
                 .await?;
             Ok(())
         }
         QueueCommand::Swap(pos1, pos2) => command_handler.swap(pos1, pos2).await,
-        QueueCommand::CreateQueueFromFile(name) => command_handler.queue_from_file(name).await,
-        QueueCommand::RandomQueue(name) => command_handler.random_queue(name).await,
+        QueueCommand::Trade(place) => command_handler.trade(place).await,
+        QueueCommand::CreateQueueFromFile(name, roster) => {
+            command_handler.queue_from_file(name, roster).await
+        }
+        QueueCommand::RandomQueue(name, roster) => command_handler.random_queue(name, roster).await,
         QueueCommand::Insert(name, index) => command_handler.insert(name, index).await,
         QueueCommand::Remove(index) => command_handler.remove(index).await,
         QueueCommand::Queuename(qname) => command_handler.set_name(qname).await,
+        QueueCommand::Slots(arg) => command_handler.slots(arg).await,
+        QueueCommand::Calendar => command_handler.calendar().await,
+        QueueCommand::Sheet(arg) => command_handler.sheet(arg).await,
+        QueueCommand::Claim(place) => command_handler.claim(place).await,
+        QueueCommand::Unclaim => command_handler.unclaim().await,
+        QueueCommand::Assign(arg) => command_handler.assign(arg).await,
+        QueueCommand::Groups(arg) => command_handler.groups(arg).await,
+        QueueCommand::Pairs(arg) => command_handler.pairs(arg).await,
+        QueueCommand::Santa(arg) => command_handler.santa(arg).await,
+        QueueCommand::Repost => command_handler.repost().await,
+        QueueCommand::Show(arg) => command_handler.show(arg).await,
+        QueueCommand::Next => command_handler.next().await,
+        QueueCommand::Skip(places) => command_handler.skip(places).await,
+        QueueCommand::Postpone(places) => command_handler.postpone(places).await,
+        QueueCommand::Absent(place) => command_handler.absent(place).await,
+        QueueCommand::Template(arg) => command_handler.template(arg).await,
+        QueueCommand::Settings => command_handler.settings().await,
+        QueueCommand::List => command_handler.list().await,
+        QueueCommand::Archive => command_handler.archive().await,
+        QueueCommand::Delete => command_handler.delete().await,
+        QueueCommand::Schedule(arg) => command_handler.schedule(arg).await,
+        QueueCommand::Roster(arg) => command_handler.roster(arg).await,
+        QueueCommand::Signup(arg) => command_handler.signup(arg).await,
+        QueueCommand::Poll(arg) => command_handler.poll(arg).await,
+        QueueCommand::QueueFromPoll(option) => command_handler.queue_from_poll(option).await,
     };
 
     match res {
         Ok(_) => {}
         Err(error::Error::NoQueueReply) => {
-            cx.answer("You must reply to a queue created by this bot for this command to work.")
+            cx.answer(Msg::NoQueueReply.text(lang))
                 .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
                 .send()
                 .await?;
         }
         Err(error::Error::Diesel(da::Error::NonexistentPosition { pos })) => {
-            cx.answer(format!("Nonexistent position: {}", pos))
+            cx.answer(Msg::NonexistentPosition { pos }.text(lang))
+                .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
+                .send()
+                .await?;
+        }
+        Err(error::Error::Diesel(da::Error::NonexistentElement { .. })) => {
+            cx.answer(Msg::ElementGone.text(lang))
+                .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
+                .send()
+                .await?;
+        }
+        Err(error::Error::NoRoster { name }) => {
+            cx.answer(Msg::NoRoster { name: &name }.text(lang))
+                .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
+                .send()
+                .await?;
+        }
+        Err(error::Error::ArchivedQueue) => {
+            cx.answer(Msg::ArchivedQueue.text(lang))
+                .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
+                .send()
+                .await?;
+        }
+        Err(error::Error::SheetQueue) => {
+            cx.answer(Msg::SheetSlotsFixed.text(lang))
+                .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
+                .send()
+                .await?;
+        }
+        Err(error::Error::OutdatedQueue { queue_id, version }) => {
+            let keyboard = confirmation_keyboard(Msg::ConfirmButton, queue_id, version, lang);
+            cx.answer(Msg::OutdatedQueue.text(lang))
                 .reply_to_message_id(cx.update.id)
+                .disable_notification(silent)
+                .reply_markup(keyboard)
                 .send()
                 .await?;
         }
@@ -160,311 +356,2330 @@ async fn answer(cx: UpdateWithCx<Bot, Message>, command: QueueCommand) -> error:
     Ok(())
 }
 
-fn format_queue(queue_name: Option<&str>, queue_elems: &[da::QueueElementForQueue]) -> String {
-    let elem = queue_elems
-        .iter()
-        .map(|x| format!("{}) {}", x.queue_place, x.element_name))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    match queue_name {
-        Some(n) => {
-            format!("{}\n{}", n, elem)
-        }
-        None => elem,
-    }
+/// Whether the user confirmed the command for the queue as it is now.
+fn is_confirmed(confirmed: Option<Confirmed>, queue: &da::Queue) -> bool {
+    matches!(
+        confirmed,
+        Some(confirmed) if confirmed.queue_id == queue.id && confirmed.version == queue.version
+    )
 }
 
-fn shuffled_queue(mut queue: Vec<da::QueueElementForQueue>) -> Vec<da::QueueElementForQueue> {
-    use rand::prelude::*;
-    queue.as_mut_slice().shuffle(&mut rand::rngs::OsRng);
-    for (i, q) in queue.iter_mut().enumerate() {
-        q.queue_place = i as i32 + 1;
-    }
-    queue
+/// Buttons which re-run the command they reply to for the queue at `version`, or cancel it.
+fn confirmation_keyboard(
+    confirm: Msg<'_>,
+    queue_id: i64,
+    version: i32,
+    lang: da::Language,
+) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::default().append_row(vec![
+        InlineKeyboardButton::callback(
+            confirm.text(lang),
+            callback::CallbackData::Confirm { queue_id, version }.to_string(),
+        ),
+        InlineKeyboardButton::callback(
+            Msg::CancelButton.text(lang),
+            callback::CallbackData::Cancel.to_string(),
+        ),
+    ])
 }
 
-fn create_bot() -> impl Future {
-    let bot = Bot::from_env();
+async fn answer_callback(
+    cx: UpdateWithCx<Bot, CallbackQuery>,
+    bot_name: String,
+    locks: locks::QueueLocks,
+) -> error::Result<()> {
+    let data = cx
+        .update
+        .data
+        .as_deref()
+        .and_then(|data| data.parse::<callback::CallbackData>().ok());
+    let message = match &cx.update.message {
+        Some(message) => message.clone(),
+        None => return Ok(()),
+    };
 
-    let bot_name = env::var(consts::BOT_NAME)
-        .unwrap_or_else(|_|panic!("You must provide the {} env variable", consts::BOT_NAME));
+    log::info!("Chat: {}; Callback: {:?}", message.chat_id(), data);
 
-    teloxide::commands_repl(bot, bot_name, answer)
-}
+    let repo = da::QueueRepository::from_connection(establish_connection());
+    let chat_settings = repo.get_settings(message.chat_id())?;
+    let lang = i18n::resolve(
+        chat_settings.language,
+        cx.update.from.language_code.as_deref(),
+    );
 
-fn establish_connection() -> PgConnection {
-    let database_url = env::var(consts::DATABASE_URL)
-        .unwrap_or_else(|_|panic!("{} must be set", consts::DATABASE_URL));
-    PgConnection::establish(&database_url).unwrap_or_else(|_|panic!("Error connecting to {}", database_url))
+    match data {
+        Some(callback::CallbackData::Page { queue_id, page }) => {
+            turn_page(&cx, &message, repo, queue_id, page, locks).await
+        }
+        Some(callback::CallbackData::Setting(setting)) => {
+            change_setting(&cx, &message, repo, chat_settings, setting).await
+        }
+        Some(callback::CallbackData::List { page }) => {
+            turn_list_page(&cx, &message, repo, lang, page).await
+        }
+        Some(callback::CallbackData::Repost { queue_id }) => {
+            repost_listed(&cx, &message, repo, chat_settings, queue_id, locks).await
+        }
+        Some(callback::CallbackData::Archive { queue_id, page }) => {
+            archive_listed(&cx, &message, repo, lang, queue_id, page).await
+        }
+        Some(callback::CallbackData::Unschedule { schedule_id }) => {
+            unschedule_listed(&cx, &message, repo, lang, schedule_id).await
+        }
+        Some(callback::CallbackData::SignUp { signup_id }) => {
+            answer_signup(&cx, &message, repo, lang, signup_id, true).await
+        }
+        Some(callback::CallbackData::LeaveSignup { signup_id }) => {
+            answer_signup(&cx, &message, repo, lang, signup_id, false).await
+        }
+        Some(callback::CallbackData::AcceptSwap { request_id }) => {
+            answer_swap(&cx, &message, repo, lang, request_id, true, locks).await
+        }
+        Some(callback::CallbackData::DeclineSwap { request_id }) => {
+            answer_swap(&cx, &message, repo, lang, request_id, false, locks).await
+        }
+        Some(callback::CallbackData::Claim {
+            queue_id,
+            element_id,
+        }) => answer_claim(&cx, &message, repo, lang, queue_id, Some(element_id), locks).await,
+        Some(callback::CallbackData::Release { queue_id }) => {
+            answer_claim(&cx, &message, repo, lang, queue_id, None, locks).await
+        }
+        Some(data) => answer_confirmation(&cx, &message, lang, data, bot_name, locks).await,
+        None => Ok(()),
+    }
 }
 
-fn create_http_server() -> impl Future {
-    let example1 = warp::get()
-    .and(warp::query::<HashMap<String, String>>())
-    .map(|p: HashMap<String, String>| match p.get("name") {
-        Some(name) => Response::builder().body(format!("Hello, {}. This HTTP triggered function executed successfully.", name)),
-        None => Response::builder().body(String::from("This HTTP triggered function executed successfully. Pass a name in the query string for a personalized response.")),
-    });
-    let port: u16 = match env::var(consts::HANDLER_PORT) {
-        Ok(val) => val.parse().expect("Custom Handler port is not a number!"),
-        Err(_) => 3000,
+async fn answer_confirmation(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    lang: da::Language,
+    data: callback::CallbackData,
+    bot_name: String,
+    locks: locks::QueueLocks,
+) -> error::Result<()> {
+    let query = &cx.update;
+    // confirmations reply to the command they were asked for
+    let command_message = match message.reply_to_message() {
+        Some(command_message) => command_message.clone(),
+        None => return Ok(()),
     };
 
-    warp::serve(example1).run((Ipv4Addr::UNSPECIFIED, port))
+    if command_message.from().map(|user| user.id) != Some(query.from.id) {
+        cx.requester
+            .answer_callback_query(query.id.clone())
+            .text(Msg::OnlyAuthor.text(lang))
+            .send()
+            .await?;
+        return Ok(());
+    }
+    cx.requester
+        .answer_callback_query(query.id.clone())
+        .send()
+        .await?;
+    cx.requester
+        .delete_message(message.chat_id(), message.id)
+        .send()
+        .await?;
+
+    if let callback::CallbackData::Confirm { queue_id, version } = data {
+        let command = command_message
+            .text()
+            .and_then(|text| QueueCommand::parse(text, &bot_name).ok());
+        if let Some(command) = command {
+            let command_cx = UpdateWithCx {
+                requester: cx.requester.clone(),
+                update: command_message,
+            };
+            let confirmed = Confirmed { queue_id, version };
+            answer(command_cx, command, locks, Some(confirmed)).await?;
+        }
+    }
+
+    Ok(())
 }
 
-pub struct CommandHandler<'a> {
+async fn turn_page(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
     repo: da::QueueRepository,
-    cx: &'a UpdateWithCx<Bot, Message>,
-    chat: da::Chat,
+    queue_id: i64,
+    page: i32,
+    locks: locks::QueueLocks,
+) -> error::Result<()> {
+    cx.requester
+        .answer_callback_query(cx.update.id.clone())
+        .send()
+        .await?;
+
+    let queue_message = match repo.get_queue_message(message.chat_id(), message.id as i64)? {
+        Some(queue_message) if queue_message.queue_id == queue_id => queue_message,
+        _ => return Ok(()),
+    };
+    let key = da::QueueKey { id: queue_id };
+
+    let _guard = locks.lock(queue_id).await?;
+
+    let queue = repo.get_queue(&key)?;
+    let queue_elems = repo.get_elements_for_queue(&key)?;
+    let template = repo.get_template(queue.chat_id)?;
+    let lang = i18n::chat_language(&repo.get_settings(queue.chat_id)?);
+    let rendered = render::render_queue(&queue, queue_elems.as_slice(), &template, lang, page);
+    let queue_message = repo.set_message_page(&queue_message, rendered.page)?;
+
+    match edit_queue_message(&cx.requester, &queue_message, rendered).await {
+        Ok(_)
+        | Err(RequestError::ApiError {
+            kind: ApiError::MessageNotModified,
+            ..
+        }) => Ok(repo.set_message_version(&queue_message, queue.version)?),
+        Err(e) => Err(e.into()),
+    }
 }
 
-impl CommandHandler<'_> {
-    pub async fn random_queue(self, name: Option<String>) -> error::Result<()> {
-        let reply_queue = self.get_reply_to_queue()?;
-
-        let queue = self.repo.get_elements_for_queue(&reply_queue.key())?;
-        let shuffled_queue_elems = shuffled_queue(queue);
-
-        let str_queue = format_queue(
-            name.as_deref(),
-            shuffled_queue_elems.as_slice(),
-        );
-        let Message { id: sent_id, .. } = self.cx.answer(str_queue).send().await?;
-
-        let queue = self.repo.create_new_queue(da::Queue {
-            id: sent_id as i64,
-            chat_id: reply_queue.chat_id,
-            qname: name,
-        })?;
-        self.repo
-            .insert_filled_queue(queue.key(), shuffled_queue_elems)?;
-
-        self.cx
-            .requester
-            .pin_chat_message(self.chat.id, sent_id)
+async fn change_setting(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    repo: da::QueueRepository,
+    mut chat_settings: da::ChatSettings,
+    setting: settings::Setting,
+) -> error::Result<()> {
+    let query = &cx.update;
+    let language_code = query.from.language_code.as_deref();
+    if !is_admin(&cx.requester, &message.chat, query.from.id).await? {
+        let lang = i18n::resolve(chat_settings.language, language_code);
+        cx.requester
+            .answer_callback_query(query.id.clone())
+            .text(Msg::OnlyAdminsSettings.text(lang))
             .send()
             .await?;
-        Ok(())
+        return Ok(());
     }
+    cx.requester
+        .answer_callback_query(query.id.clone())
+        .send()
+        .await?;
+
+    settings::toggle(&mut chat_settings, setting);
+    let chat_settings = repo.update_settings(&chat_settings)?;
+    let lang = i18n::resolve(chat_settings.language, language_code);
+
+    cx.requester
+        .edit_message_text(message.chat_id(), message.id, Msg::SettingsTitle.text(lang))
+        .reply_markup(settings::settings_keyboard(&chat_settings, lang))
+        .send()
+        .await?;
+    Ok(())
+}
 
-    pub async fn insert(self, name: String, index: Option<i32>) -> error::Result<()> {
-        let reply_queue = self.get_reply_to_queue()?;
-
-        self.repo
-            .insert_new_elem(&reply_queue.key(), name.clone(), index)?;
-
-        let queue_elem = self.repo.get_elements_for_queue(&reply_queue.key())?;
-
-        let str_queue = format_queue(
-            reply_queue.qname.as_deref(),
-            queue_elem.as_slice(),
-        );
+async fn turn_list_page(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    repo: da::QueueRepository,
+    lang: da::Language,
+    page: i64,
+) -> error::Result<()> {
+    cx.requester
+        .answer_callback_query(cx.update.id.clone())
+        .send()
+        .await?;
+
+    let listing = queue_list(&repo, &message.chat, lang, page)?;
+    edit_queue_list(&cx.requester, message, listing).await
+}
 
-        self.cx
-            .requester
-            .edit_message_text(self.chat.id, reply_queue.id as i32, str_queue)
-            .send()
-            .await?;
+async fn repost_listed(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    repo: da::QueueRepository,
+    chat_settings: da::ChatSettings,
+    queue_id: i64,
+    locks: locks::QueueLocks,
+) -> error::Result<()> {
+    cx.requester
+        .answer_callback_query(cx.update.id.clone())
+        .send()
+        .await?;
+
+    let key = da::QueueKey { id: queue_id };
+    let _guard = locks.lock(queue_id).await?;
+
+    let queue = repo.get_queue(&key)?;
+    if queue.chat_id != message.chat_id() || queue.archived {
+        return Ok(());
+    }
+    let queue_elems = repo.get_elements_for_queue(&key)?;
+    let template = repo.get_template(queue.chat_id)?;
+    let lang = i18n::chat_language(&chat_settings);
+    let rendered = render::render_queue(&queue, queue_elems.as_slice(), &template, lang, 0);
+    let page = rendered.page;
+
+    let sent =
+        send_rendered_queue(&cx.requester, queue.chat_id, rendered, chat_settings.silent).await?;
+    repo.add_queue_message(da::QueueMessage {
+        message_id: sent.id as i64,
+        chat_id: queue.chat_id,
+        queue_id: queue.id,
+        page,
+        version: queue.version,
+    })?;
+    Ok(())
+}
 
-        self.cx
-            .answer(format!(
-                "Inserted {} at {}",
-                name,
-                index
-                    .map(|x| x.to_string())
-                    .unwrap_or_else(||"the last position".to_string())
-            ))
-            .reply_to_message_id(self.cx.update.id)
+async fn archive_listed(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    repo: da::QueueRepository,
+    lang: da::Language,
+    queue_id: i64,
+    page: i64,
+) -> error::Result<()> {
+    let query = &cx.update;
+    if !is_admin(&cx.requester, &message.chat, query.from.id).await? {
+        cx.requester
+            .answer_callback_query(query.id.clone())
+            .text(Msg::OnlyAdminsArchive.text(lang))
             .send()
             .await?;
-
-        Ok(())
+        return Ok(());
     }
 
-    pub async fn remove(self, index: i32) -> error::Result<()> {
-        let reply_queue = self.get_reply_to_queue()?;
-
-        let removed_name = self.repo.remove_elem(&reply_queue.key(), index)?;
-
-        let queue_elem = self.repo.get_elements_for_queue(&reply_queue.key())?;
-        let str_queue = format_queue(
-            reply_queue.qname.as_deref(),
-            queue_elem.as_slice(),
-        );
+    let key = da::QueueKey { id: queue_id };
+    if repo.get_queue(&key)?.chat_id == message.chat_id() {
+        repo.archive_queue(&key)?;
+    }
+    cx.requester
+        .answer_callback_query(query.id.clone())
+        .text(Msg::Archived.text(lang))
+        .send()
+        .await?;
+
+    let listing = queue_list(&repo, &message.chat, lang, page)?;
+    edit_queue_list(&cx.requester, message, listing).await
+}
 
-        self.cx
-            .requester
-            .edit_message_text(self.chat.id, reply_queue.id as i32, str_queue)
+async fn unschedule_listed(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    repo: da::QueueRepository,
+    lang: da::Language,
+    schedule_id: i64,
+) -> error::Result<()> {
+    let query = &cx.update;
+    if !is_admin(&cx.requester, &message.chat, query.from.id).await? {
+        cx.requester
+            .answer_callback_query(query.id.clone())
+            .text(Msg::OnlyAdminsSchedule.text(lang))
             .send()
             .await?;
+        return Ok(());
+    }
 
-        self.cx
-            .answer(format!("Removed {} from {}", removed_name, index))
-            .reply_to_message_id(self.cx.update.id)
-            .send()
-            .await?;
+    repo.delete_schedule(message.chat_id(), schedule_id)?;
+    cx.requester
+        .answer_callback_query(query.id.clone())
+        .text(Msg::Unscheduled.text(lang))
+        .send()
+        .await?;
 
-        Ok(())
+    let schedules = repo.list_schedules(message.chat_id())?;
+    let listing = render::render_schedule_list(&schedules, lang);
+    edit_queue_list(&cx.requester, message, listing).await
+}
+
+/// Signs the user up or takes them off, then shows the change in the message.
+async fn answer_signup(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    repo: da::QueueRepository,
+    lang: da::Language,
+    signup_id: i64,
+    join: bool,
+) -> error::Result<()> {
+    let query = &cx.update;
+    let user = &query.from;
+    let res = if join {
+        repo.add_signup_entry(signup_id, user.id, user.full_name())
+    } else {
+        repo.remove_signup_entry(signup_id, user.id)
+    };
+    let (text, changed) = match (res, join) {
+        (Ok(true), true) => (Msg::SignedUp, true),
+        (Ok(true), false) => (Msg::LeftSignup, true),
+        (Ok(false), true) => (Msg::AlreadySignedUp, false),
+        (Ok(false), false) => (Msg::NotSignedUp, false),
+        (Err(da::Error::SignupClosed), _) => (Msg::SignupIsClosed, false),
+        (Err(e), _) => return Err(e.into()),
+    };
+    cx.requester
+        .answer_callback_query(query.id.clone())
+        .text(text.text(lang))
+        .send()
+        .await?;
+
+    if !changed {
+        return Ok(());
     }
+    let signup = match repo.find_signup(signup_id)? {
+        Some(signup) => signup,
+        None => return Ok(()),
+    };
+    let entries = repo.get_signup_entries(signup_id)?;
+    let rendered = render::render_signup(&signup, &entries, lang);
+    edit_queue_list(&cx.requester, message, rendered).await
+}
 
-    pub async fn queue_from_file(self, name: Option<String>) -> error::Result<()> {
-        let doc = match self
-            .cx
-            .update
-            .reply_to_message()
-            .map(|reply| reply.document())
-            .flatten()
-        {
-            Some(doc) => doc,
-            None => {
-                self.cx
-                    .answer("Please reply to a message with a file.")
-                    .reply_to_message_id(self.cx.update.id)
-                    .send()
-                    .await?;
-                return Ok(());
-            }
+/// Claims the slot of a sheet for the user, or releases their slot for `None`.
+async fn answer_claim(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    mut repo: da::QueueRepository,
+    lang: da::Language,
+    queue_id: i64,
+    element_id: Option<i64>,
+    locks: locks::QueueLocks,
+) -> error::Result<()> {
+    let query = &cx.update;
+    let key = da::QueueKey { id: queue_id };
+    let _guard = locks.lock(queue_id).await?;
+
+    let queue = repo.get_queue(&key)?;
+    let text = if queue.chat_id != message.chat_id() || !queue.sheet {
+        Msg::NotASheet
+    } else if queue.archived {
+        Msg::ArchivedQueue
+    } else {
+        let res = match element_id {
+            Some(element_id) => repo
+                .claim_slot(
+                    &key,
+                    da::ElementRef::Id(element_id),
+                    query.from.id,
+                    query.from.full_name(),
+                )
+                .map(|_| Msg::SlotClaimed),
+            None => repo
+                .release_slot(&key, query.from.id)
+                .map(|released| match released {
+                    Some(_) => Msg::SlotReleased,
+                    None => Msg::NoSlotClaimed,
+                }),
         };
+        match res {
+            Ok(text) => text,
+            Err(da::Error::SlotTaken) => Msg::SlotTaken,
+            Err(da::Error::AlreadyClaimed) => Msg::AlreadyClaimed,
+            Err(da::Error::NonexistentElement { .. }) => Msg::ElementGone,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    cx.requester
+        .answer_callback_query(query.id.clone())
+        .text(text.text(lang))
+        .send()
+        .await?;
+
+    if let Msg::SlotClaimed | Msg::SlotReleased = text {
+        update_queue_messages(&cx.requester, &mut repo, &key).await?;
+    }
+    Ok(())
+}
 
-        let File { file_path, .. } = self
-            .cx
-            .requester
-            .get_file(doc.file_id.clone())
+/// Trades places once the owner of the other element accepts.
+/// The user who asked can decline their own request to take it back.
+async fn answer_swap(
+    cx: &UpdateWithCx<Bot, CallbackQuery>,
+    message: &Message,
+    mut repo: da::QueueRepository,
+    lang: da::Language,
+    request_id: i64,
+    accept: bool,
+    locks: locks::QueueLocks,
+) -> error::Result<()> {
+    let query = &cx.update;
+    let request = match repo.find_swap_request(request_id)? {
+        Some(request) if request.chat_id == message.chat_id() => request,
+        _ => {
+            cx.requester
+                .answer_callback_query(query.id.clone())
+                .send()
+                .await?;
+            return edit_swap_request(&cx.requester, message, Msg::SwapRequestGone, lang).await;
+        }
+    };
+    let key = request.queue_key();
+    let (from, to) = match (
+        find_swapped_element(&repo, &key, request.from_element_id)?,
+        find_swapped_element(&repo, &key, request.to_element_id)?,
+    ) {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            cx.requester
+                .answer_callback_query(query.id.clone())
+                .text(Msg::SwapElementGone.text(lang))
+                .send()
+                .await?;
+            repo.take_swap_request(request_id)?;
+            return edit_swap_request(&cx.requester, message, Msg::SwapElementGone, lang).await;
+        }
+    };
+
+    let user = Some(query.from.id);
+    if to.user_id != user && (accept || from.user_id != user) {
+        cx.requester
+            .answer_callback_query(query.id.clone())
+            .text(Msg::NotYourSwap.text(lang))
             .send()
             .await?;
-        let mut file_data = Vec::new();
-        self.cx
-            .requester
-            .download_file(&file_path, &mut file_data)
-            .await?;
-
-        let str: &str = from_utf8(file_data.as_slice())?;
-
-        let queue_elems = str
-            .lines()
-            .enumerate()
-            .map(|(i, x)| (i + 1, x.trim()))
-            .map(|(i, x)| da::QueueElementForQueue {
-                element_name: x.to_string(),
-                queue_place: i as i32,
-            })
-            .collect::<Vec<_>>();
+        return Ok(());
+    }
+    cx.requester
+        .answer_callback_query(query.id.clone())
+        .send()
+        .await?;
 
-        let str_queue = format_queue(name.as_deref(), queue_elems.as_slice());
-        let Message { id: sent_id, .. } = self.cx.answer(str_queue).send().await?;
+    let _guard = locks.lock(request.queue_id).await?;
 
-        let queue = self.repo.create_new_queue(da::Queue {
-            id: sent_id as i64,
-            chat_id: self.chat.id,
-            qname: name,
-        })?;
-        self.repo.insert_filled_queue(queue.key(), queue_elems)?;
-        Ok(())
+    // the other button could have been pressed while waiting for the lock
+    if repo.take_swap_request(request_id)?.is_none() {
+        return edit_swap_request(&cx.requester, message, Msg::SwapRequestGone, lang).await;
+    }
+    if !accept {
+        let answer = if to.user_id == user {
+            Msg::SwapDeclined
+        } else {
+            Msg::SwapWithdrawn
+        };
+        return edit_swap_request(&cx.requester, message, answer, lang).await;
+    }
+    if repo.get_queue(&key)?.archived {
+        return edit_swap_request(&cx.requester, message, Msg::ArchivedQueue, lang).await;
     }
 
-    pub async fn swap(self, pos1: i32, pos2: i32) -> error::Result<()> {
-        if pos1 == pos2 {
-            self.cx
-                .answer("Can't swap position with itself")
-                .reply_to_message_id(self.cx.update.id)
-                .send()
-                .await?;
-            return Ok(());
+    let swapped =
+        repo.swap_positions_for_queue(&key, da::ElementRef::Id(from.id), da::ElementRef::Id(to.id));
+    let (elem1, elem2) = match swapped {
+        Ok(swapped) => swapped,
+        // removed while waiting for the lock
+        Err(da::Error::NonexistentElement { .. }) => {
+            return edit_swap_request(&cx.requester, message, Msg::SwapElementGone, lang).await;
         }
+        Err(e) => return Err(e.into()),
+    };
+    update_queue_messages(&cx.requester, &mut repo, &key).await?;
 
-        let reply_queue = self.get_reply_to_queue()?;
-
-        self.repo
-            .swap_positions_for_queue(&reply_queue.key(), pos1, pos2)?;
+    let text = Msg::SwapAccepted {
+        name1: &element_mention(&elem1),
+        name2: &element_mention(&elem2),
+    }
+    .text(lang);
+    cx.requester
+        .edit_message_text(message.chat_id(), message.id, text)
+        .parse_mode(ParseMode::Html)
+        .send()
+        .await?;
+    Ok(())
+}
 
-        let queue: Vec<da::QueueElementForQueue> =
-            self.repo.get_elements_for_queue(&reply_queue.key())?;
-        let str_queue = format_queue(
-            reply_queue.qname.as_deref(),
-            queue.as_slice(),
-        );
+/// `None` if the element was removed from the queue after the request was made.
+fn find_swapped_element(
+    repo: &da::QueueRepository,
+    key: &da::QueueKey,
+    id: i64,
+) -> error::Result<Option<da::QueueElement>> {
+    match repo.find_element(key, da::ElementRef::Id(id)) {
+        Ok(elem) => Ok(Some(elem)),
+        Err(da::Error::NonexistentElement { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
 
-        self.cx
-            .requester
-            .edit_message_text(self.chat.id, reply_queue.id as i32, str_queue)
-            .send()
-            .await?;
+/// Replaces a request of `/trade` with how it ended, taking the buttons off.
+async fn edit_swap_request(
+    bot: &Bot,
+    message: &Message,
+    text: Msg<'_>,
+    lang: da::Language,
+) -> error::Result<()> {
+    bot.edit_message_text(message.chat_id(), message.id, text.text(lang))
+        .send()
+        .await?;
+    Ok(())
+}
 
-        let pos1_name = &queue
-            .iter()
-            .find(|x| x.queue_place == pos1)
-            .unwrap()
-            .element_name;
-        let pos2_name = &queue
-            .iter()
-            .find(|x| x.queue_place == pos2)
-            .unwrap()
-            .element_name;
+/// Renders a page of `/list`, pages past the end are clamped.
+fn queue_list(
+    repo: &da::QueueRepository,
+    chat: &Chat,
+    lang: da::Language,
+    page: i64,
+) -> error::Result<render::RenderedList> {
+    let total = repo.count_queues(chat.id)?;
+    let last_page = (total - 1).max(0) / render::LIST_PAGE_SIZE;
+    let page = page.clamp(0, last_page);
+    let queues = repo.list_queues(
+        chat.id,
+        page * render::LIST_PAGE_SIZE,
+        render::LIST_PAGE_SIZE,
+    )?;
+    Ok(render::render_queue_list(chat, &queues, total, page, lang))
+}
 
-        self.cx
-            .answer(format!(
-                "Swapped {} ({}) and {} ({})",
-                pos2_name, pos1, pos1_name, pos2
-            ))
-            .reply_to_message_id(self.cx.update.id)
-            .send()
-            .await?;
+async fn edit_queue_list(
+    bot: &Bot,
+    message: &Message,
+    listing: render::RenderedList,
+) -> error::Result<()> {
+    let res = bot
+        .edit_message_text(message.chat_id(), message.id, listing.text)
+        .parse_mode(ParseMode::Html)
+        .disable_web_page_preview(true)
+        .reply_markup(listing.keyboard)
+        .send()
+        .await;
+    match res {
+        Err(RequestError::ApiError {
+            kind: ApiError::MessageNotModified,
+            ..
+        }) => Ok(()),
+        res => Ok(res.map(|_| ())?),
+    }
+}
 
-        Ok(())
+/// Whether the user administers the chat, anyone does in private chats.
+async fn is_admin(bot: &Bot, chat: &Chat, user_id: i64) -> Result<bool, RequestError> {
+    if chat.is_private() {
+        return Ok(true);
     }
+    let member = bot.get_chat_member(chat.id, user_id).send().await?;
+    Ok(member.kind.is_privileged())
+}
 
-    pub async fn set_name(self, qname: String) -> error::Result<()> {
-        let reply_queue = self.get_reply_to_queue()?;
-        match reply_queue.qname {
-            Some(old_name) if old_name == qname => {
-                self.cx
-                    .answer("Can't rename to the same name.")
-                    .reply_to_message_id(self.cx.update.id)
-                    .send()
-                    .await?;
-                return Ok(());
+/// Re-renders every message of the queue. Messages which were deleted
+/// in the chat are forgotten.
+async fn update_queue_messages(
+    bot: &Bot,
+    repo: &mut da::QueueRepository,
+    queue: &da::QueueKey,
+) -> error::Result<()> {
+    let queue = repo.get_queue(queue)?;
+    let lang = i18n::chat_language(&repo.get_settings(queue.chat_id)?);
+    let queue_elems = repo.get_elements_for_queue(&queue.key())?;
+    let template = repo.get_template(queue.chat_id)?;
+
+    for message in repo.get_queue_messages(&queue.key())? {
+        let rendered = render::render_queue(
+            &queue,
+            queue_elems.as_slice(),
+            &template,
+            lang,
+            message.page,
+        );
+        // the queue could have shrunk below the page of the message
+        let message = if rendered.page == message.page {
+            message
+        } else {
+            repo.set_message_page(&message, rendered.page)?
+        };
+
+        match edit_queue_message(bot, &message, rendered).await {
+            Ok(_)
+            | Err(RequestError::ApiError {
+                kind: ApiError::MessageNotModified,
+                ..
+            }) => repo.set_message_version(&message, queue.version)?,
+            Err(RequestError::ApiError {
+                kind: ApiError::MessageToEditNotFound,
+                ..
+            }) => {
+                log::info!(
+                    "Queue {} message {} is gone, forgetting it",
+                    queue.id,
+                    message.message_id
+                );
+                repo.remove_queue_message(&message)?;
             }
-            _ => {}
+            Err(e) => return Err(e.into()),
         }
+    }
+    Ok(())
+}
 
-        let new_name = self.repo.set_queue_name(&reply_queue.key(), qname)?;
+async fn edit_queue_message(
+    bot: &Bot,
+    message: &da::QueueMessage,
+    rendered: render::RenderedQueue,
+) -> Result<(), RequestError> {
+    let mut request = bot
+        .edit_message_text(message.chat_id, message.message_id as i32, rendered.text)
+        .parse_mode(ParseMode::Html);
+    if let Some(keyboard) = rendered.keyboard {
+        request = request.reply_markup(keyboard);
+    }
+    request.send().await?;
+    Ok(())
+}
 
-        let queue: Vec<da::QueueElementForQueue> =
-            self.repo.get_elements_for_queue(&reply_queue.key())?;
-        let str_queue = format_queue(Some(new_name.as_str()), queue.as_slice());
+/// Creates a queue of the elements, posts it and pins it if the chat settings say so.
+async fn post_new_queue(
+    bot: &Bot,
+    repo: &mut da::QueueRepository,
+    settings: &da::ChatSettings,
+    new_queue: da::NewQueue,
+    elements: Vec<da::ElementData>,
+) -> error::Result<da::Queue> {
+    let queue = repo.create_new_queue(new_queue)?;
+    let queue_elems = repo.insert_filled_queue(queue.key(), elements)?;
+
+    post_queue(bot, repo, settings, &queue, &queue_elems).await?;
+    Ok(queue)
+}
 
-        self.cx
-            .requester
-            .edit_message_text(self.chat.id, reply_queue.id as i32, str_queue)
+/// Posts a just created queue and pins it if the chat settings say so.
+async fn post_queue(
+    bot: &Bot,
+    repo: &mut da::QueueRepository,
+    settings: &da::ChatSettings,
+    queue: &da::Queue,
+    queue_elems: &[da::QueueElementForQueue],
+) -> error::Result<()> {
+    let sent_id = send_queue(bot, repo, queue, queue_elems, settings.silent, 0).await?;
+    if settings.auto_pin {
+        bot.pin_chat_message(queue.chat_id, sent_id)
+            .disable_notification(settings.silent)
             .send()
             .await?;
+    }
+    Ok(())
+}
 
-        self.cx
-            .answer(format!(
-                "Renamed queue from {} to {}",
-                reply_queue.qname.unwrap_or_else(|| "`empty`".to_string()),
-                new_name
-            ))
-            .reply_to_message_id(self.cx.update.id)
-            .send()
-            .await?;
+/// Posts a page of the queue as a new message which is kept up to date.
+async fn send_queue(
+    bot: &Bot,
+    repo: &mut da::QueueRepository,
+    queue: &da::Queue,
+    queue_elems: &[da::QueueElementForQueue],
+    silent: bool,
+    page: i32,
+) -> error::Result<i32> {
+    let lang = i18n::chat_language(&repo.get_settings(queue.chat_id)?);
+    let template = repo.get_template(queue.chat_id)?;
+    let rendered = render::render_queue(queue, queue_elems, &template, lang, page);
+    let page = rendered.page;
+    let Message { id: sent_id, .. } =
+        send_rendered_queue(bot, queue.chat_id, rendered, silent).await?;
+
+    repo.add_queue_message(da::QueueMessage {
+        message_id: sent_id as i64,
+        chat_id: queue.chat_id,
+        queue_id: queue.id,
+        page,
+        version: queue.version,
+    })?;
+    Ok(sent_id)
+}
 
-        Ok(())
+async fn send_rendered_queue(
+    bot: &Bot,
+    chat_id: i64,
+    rendered: render::RenderedQueue,
+    silent: bool,
+) -> Result<Message, RequestError> {
+    let mut request = bot
+        .send_message(chat_id, rendered.text)
+        .parse_mode(ParseMode::Html)
+        .disable_notification(silent);
+    if let Some(keyboard) = rendered.keyboard {
+        request = request.reply_markup(keyboard);
     }
+    request.send().await
+}
+
+/// The non-empty lines of a text, trimmed.
+fn nonempty_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|x| !x.is_empty())
+}
+
+/// Parses the name of a sheet and the labels of its slots written on the next lines,
+/// or `count [name]` for numbered slots.
+fn parse_sheet(text: &str) -> Option<(Option<String>, Vec<Option<String>>)> {
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let first = first.trim();
+    let labels = nonempty_lines(rest)
+        .map(|x| Some(x.to_string()))
+        .collect::<Vec<_>>();
+
+    let (name, labels) = if labels.is_empty() {
+        let (count, name) = first.split_once(char::is_whitespace).unwrap_or((first, ""));
+        let count: usize = count.parse().ok()?;
+        (name.trim(), vec![None; count.min(MAX_SHEET_SLOTS + 1)])
+    } else {
+        (first, labels)
+    };
+    if !(1..=MAX_SHEET_SLOTS).contains(&labels.len()) {
+        return None;
+    }
+    Some((
+        Some(name).filter(|x| !x.is_empty()).map(str::to_string),
+        labels,
+    ))
+}
+
+/// The name of the element in HTML, mentioning its user if it has one.
+fn element_mention(elem: &da::QueueElement) -> String {
+    match elem.user_id {
+        Some(user_id) => html::user_mention(user_id, &elem.element_name),
+        None => html::escape(&elem.element_name),
+    }
+}
+
+/// Says whose turn it is in HTML, or that the queue is over.
+fn turn_text(current: Option<&da::QueueElement>, lang: da::Language) -> String {
+    match current {
+        Some(current) => Msg::Turn {
+            name: &element_mention(current),
+        }
+        .text(lang),
+        None => Msg::QueueOver.text(lang),
+    }
+}
+
+fn shuffle_elements(elements: &mut [da::ElementData]) {
+    use rand::prelude::*;
+    elements.shuffle(&mut rand::rngs::OsRng);
+}
+
+/// Sets one option of `/template`, on failure returns what to answer the user.
+fn apply_template_option<'a>(
+    template: &mut da::QueueTemplate,
+    option: &'a str,
+    value: &str,
+) -> Result<(), Msg<'a>> {
+    let value_len = value.chars().count();
+    match option {
+        "numbering" => template.numbering = value.parse().map_err(|_| Msg::NumberingValues)?,
+        "waiting" | "current" | "done" | "skipped" | "absent"
+            if value_len > render::MAX_MARK_LEN =>
+        {
+            return Err(Msg::MarkTooLong {
+                max: render::MAX_MARK_LEN,
+            });
+        }
+        "waiting" => template.waiting_mark = value.to_string(),
+        "current" => template.current_mark = value.to_string(),
+        "done" => template.done_mark = value.to_string(),
+        "skipped" => template.skipped_mark = value.to_string(),
+        "absent" => template.absent_mark = value.to_string(),
+        "header" | "footer" if value_len > render::MAX_TEMPLATE_TEXT_LEN => {
+            return Err(Msg::TemplateTextTooLong {
+                option,
+                max: render::MAX_TEMPLATE_TEXT_LEN,
+            });
+        }
+        "header" => template.header = value.to_string(),
+        "footer" => template.footer = value.to_string(),
+        "counts" => {
+            template.show_counts = match value {
+                "on" => true,
+                "off" => false,
+                _ => return Err(Msg::CountsValues),
+            }
+        }
+        _ => return Err(Msg::UnknownTemplateOption { option }),
+    }
+    Ok(())
+}
+
+fn describe_template(template: &da::QueueTemplate) -> String {
+    let or_none = |x: &str| {
+        if x.is_empty() {
+            "none".to_string()
+        } else {
+            x.to_string()
+        }
+    };
+    format!(
+        "numbering: {}\nwaiting: {}\ncurrent: {}\ndone: {}\nskipped: {}\nabsent: {}\nheader: {}\nfooter: {}\ncounts: {}",
+        template.numbering.as_str(),
+        or_none(&template.waiting_mark),
+        or_none(&template.current_mark),
+        or_none(&template.done_mark),
+        or_none(&template.skipped_mark),
+        or_none(&template.absent_mark),
+        or_none(&template.header),
+        or_none(&template.footer),
+        if template.show_counts { "on" } else { "off" },
+    )
+}
+
+async fn create_bot(bot: Bot, locks: locks::QueueLocks) {
+    let bot_name = env::var(consts::BOT_NAME)
+        .unwrap_or_else(|_| panic!("You must provide the {} env variable", consts::BOT_NAME));
+
+    let callback_locks = locks.clone();
+    let callback_bot_name = bot_name.clone();
+
+    Dispatcher::new(bot)
+        .messages_handler(move |rx: DispatcherHandlerRx<Bot, Message>| {
+            UnboundedReceiverStream::new(rx)
+                .commands::<QueueCommand, _>(bot_name)
+                .for_each_concurrent(None, move |(cx, command)| {
+                    let locks = locks.clone();
+                    async move {
+                        answer(cx, command, locks, None).await.log_on_error().await;
+                    }
+                })
+        })
+        .callback_queries_handler(move |rx: DispatcherHandlerRx<Bot, CallbackQuery>| {
+            UnboundedReceiverStream::new(rx).for_each_concurrent(None, move |cx| {
+                let locks = callback_locks.clone();
+                let bot_name = callback_bot_name.clone();
+                async move {
+                    answer_callback(cx, bot_name, locks)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+            })
+        })
+        .poll_answers_handler(|rx: DispatcherHandlerRx<Bot, PollAnswer>| {
+            UnboundedReceiverStream::new(rx).for_each_concurrent(None, |cx| async move {
+                answer_poll(cx).await.log_on_error().await;
+            })
+        })
+        .setup_ctrlc_handler()
+        .dispatch()
+        .await;
+}
+
+/// Unpins the messages of a queue which is gone or archived.
+async fn unpin_queue_messages(bot: &Bot, messages: &[da::QueueMessage]) {
+    for message in messages {
+        let res = bot
+            .unpin_chat_message(message.chat_id)
+            .message_id(message.message_id as i32)
+            .send()
+            .await;
+        // the message could have never been pinned, or be gone already
+        if let Err(e) = res {
+            log::info!(
+                "Couldn't unpin message {} of queue {}: {}",
+                message.message_id,
+                message.queue_id,
+                e
+            );
+        }
+    }
+}
+
+/// Remembers the vote, so `/queuefrompoll` can make a queue of the voters later.
+async fn answer_poll(cx: UpdateWithCx<Bot, PollAnswer>) -> error::Result<()> {
+    let answer = cx.update;
+    log::info!(
+        "Poll: {}; User: {}; Options: {:?}",
+        answer.poll_id,
+        answer.user.id,
+        answer.option_ids
+    );
+
+    let repo = da::QueueRepository::from_connection(establish_connection());
+    repo.save_poll_vote(da::NewPollVote {
+        element_name: answer.user.full_name(),
+        poll_id: answer.poll_id,
+        user_id: answer.user.id,
+        option_ids: answer.option_ids,
+    })?;
+    Ok(())
+}
+
+fn establish_connection() -> PgConnection {
+    let database_url = env::var(consts::DATABASE_URL)
+        .unwrap_or_else(|_| panic!("{} must be set", consts::DATABASE_URL));
+    PgConnection::establish(&database_url)
+        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
+}
+
+fn create_http_server() -> impl Future {
+    let example1 = warp::get()
+    .and(warp::query::<HashMap<String, String>>())
+    .map(|p: HashMap<String, String>| match p.get("name") {
+        Some(name) => Response::builder().body(format!("Hello, {}. This HTTP triggered function executed successfully.", name)),
+        None => Response::builder().body(String::from("This HTTP triggered function executed successfully. Pass a name in the query string for a personalized response.")),
+    });
+    let port: u16 = match env::var(consts::HANDLER_PORT) {
+        Ok(val) => val.parse().expect("Custom Handler port is not a number!"),
+        Err(_) => 3000,
+    };
+
+    warp::serve(example1).run((Ipv4Addr::UNSPECIFIED, port))
+}
+
+/// Telegram limits the number of poll options.
+const MIN_POLL_OPTIONS: usize = 2;
+const MAX_POLL_OPTIONS: usize = 10;
+/// Sheets with more slots are better made as several.
+const MAX_SHEET_SLOTS: usize = 100;
+/// Most teams `/groups` saves as queues at once.
+const MAX_SAVED_GROUPS: usize = 10;
+
+pub struct CommandHandler<'a> {
+    repo: da::QueueRepository,
+    cx: &'a UpdateWithCx<Bot, Message>,
+    chat: da::Chat,
+    settings: da::ChatSettings,
+    /// The language of replies and of rendered queues.
+    lang: da::Language,
+    locks: locks::QueueLocks,
+    confirmed: Option<Confirmed>,
+}
+
+impl CommandHandler<'_> {
+    pub async fn random_queue(
+        mut self,
+        name: Option<String>,
+        roster: Option<String>,
+    ) -> error::Result<()> {
+        let mut shuffled_elems = match roster {
+            Some(roster) => self.roster_members(&roster)?,
+            None => {
+                let reply_queue = self.get_reply_to_queue()?;
+                self.repo.get_people_of_queue(&reply_queue)?
+            }
+        };
+        shuffle_elements(&mut shuffled_elems);
+
+        let new_queue = da::NewQueue {
+            chat_id: self.chat.id,
+            qname: name,
+        };
+        post_new_queue(
+            &self.cx.requester,
+            &mut self.repo,
+            &self.settings,
+            new_queue,
+            shuffled_elems,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn insert(mut self, name: String, index: Option<i32>) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_ordered_queue().await?;
+
+        if index.is_some() {
+            self.check_version(&reply_queue)?;
+        }
+
+        let element = match (name.is_empty(), self.cx.update.from()) {
+            (true, Some(user)) => da::ElementData {
+                element_name: user.full_name(),
+                user_id: Some(user.id),
+            },
+            _ => da::ElementData::named(name),
+        };
+        let name = element.element_name.clone();
+
+        self.repo
+            .insert_new_elem(&reply_queue.key(), element, index)?;
+
+        self.update_queue_messages(&reply_queue.key()).await?;
+
+        self.confirm(Msg::Inserted {
+            name: &name,
+            place: index,
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(mut self, index: i32) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_ordered_queue().await?;
+
+        self.check_version(&reply_queue)?;
+
+        let removed = self
+            .repo
+            .remove_elem(&reply_queue.key(), da::ElementRef::Place(index))?;
+
+        self.update_queue_messages(&reply_queue.key()).await?;
+
+        self.confirm(Msg::Removed {
+            name: &removed.element_name,
+            place: index,
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn queue_from_file(
+        mut self,
+        name: Option<String>,
+        roster: Option<String>,
+    ) -> error::Result<()> {
+        let mut elements = match roster {
+            Some(roster) => self.roster_members(&roster)?,
+            None => match self.reply_file_text().await? {
+                Some(text) => text
+                    .lines()
+                    .map(|x| da::ElementData::named(x.trim().to_string()))
+                    .collect(),
+                None => {
+                    self.reply(Msg::ReplyToFile.text(self.lang)).send().await?;
+                    return Ok(());
+                }
+            },
+        };
+        if self.settings.shuffle == da::ShuffleMode::Shuffle {
+            shuffle_elements(&mut elements);
+        }
+
+        let new_queue = da::NewQueue {
+            chat_id: self.chat.id,
+            qname: name,
+        };
+        post_new_queue(
+            &self.cx.requester,
+            &mut self.repo,
+            &self.settings,
+            new_queue,
+            elements,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn trade(mut self, place: i32) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_ordered_queue().await?;
+        let key = reply_queue.key();
+
+        self.check_version(&reply_queue)?;
+
+        let own = match self.own_element(&key)? {
+            Some(own) => own,
+            None => {
+                self.reply(Msg::NotInQueue.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        let other = self.repo.find_element(&key, da::ElementRef::Place(place))?;
+        if other.user_id == own.user_id {
+            self.reply(Msg::SwapWithItself.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+        if other.user_id.is_none() {
+            self.reply(
+                Msg::SwapNoOwner {
+                    name: &other.element_name,
+                }
+                .text(self.lang),
+            )
+            .send()
+            .await?;
+            return Ok(());
+        }
+
+        let request = self.repo.create_swap_request(da::NewSwapRequest {
+            queue_id: key.id,
+            chat_id: self.chat.id,
+            from_element_id: own.id,
+            to_element_id: other.id,
+            expires_at: trade::expires_at(Utc::now()),
+        })?;
+        let text = Msg::SwapRequest {
+            to: &element_mention(&other),
+            from: &element_mention(&own),
+            minutes: trade::REQUEST_MINUTES,
+        }
+        .text(self.lang);
+        let sent = self
+            .reply(text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(render::swap_request_keyboard(request.id, self.lang))
+            .send()
+            .await?;
+        self.repo
+            .set_swap_request_message(request.id, sent.id as i64)?;
+
+        Ok(())
+    }
+
+    pub async fn swap(mut self, pos1: i32, pos2: i32) -> error::Result<()> {
+        if pos1 == pos2 {
+            self.reply(Msg::SwapWithItself.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsSwap.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let (reply_queue, _guard) = self.lock_ordered_queue().await?;
+
+        self.check_version(&reply_queue)?;
+
+        let (elem1, elem2) = self.repo.swap_positions_for_queue(
+            &reply_queue.key(),
+            da::ElementRef::Place(pos1),
+            da::ElementRef::Place(pos2),
+        )?;
+
+        self.update_queue_messages(&reply_queue.key()).await?;
+
+        self.confirm(Msg::Swapped {
+            name1: &elem2.element_name,
+            place1: pos1,
+            name2: &elem1.element_name,
+            place2: pos2,
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_name(mut self, qname: String) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_mutable_queue().await?;
+        match reply_queue.qname {
+            Some(old_name) if old_name == qname => {
+                self.reply(Msg::SameName.text(self.lang)).send().await?;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let new_name = self.repo.set_queue_name(&reply_queue.key(), qname)?;
+
+        self.update_queue_messages(&reply_queue.key()).await?;
+
+        self.confirm(Msg::Renamed {
+            old: reply_queue.qname.as_deref(),
+            new: &new_name,
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn slots(mut self, arg: Option<String>) -> error::Result<()> {
+        let slots = match arg.as_deref() {
+            Some("off") => None,
+            Some(arg) => match slots::parse(arg, Utc::now()) {
+                Some(slots) => Some(slots),
+                None => {
+                    self.reply(Msg::SlotsUsage.text(self.lang)).send().await?;
+                    return Ok(());
+                }
+            },
+            None => {
+                self.reply(Msg::SlotsUsage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        let (reply_queue, _guard) = self.lock_mutable_queue().await?;
+
+        self.repo.set_queue_slots(&reply_queue.key(), slots)?;
+
+        self.update_queue_messages(&reply_queue.key()).await?;
+
+        match slots {
+            Some(slots) => {
+                let start = slots::slot_start(&slots, 1).format("%Y-%m-%d %H:%M");
+                self.confirm(Msg::SlotsSet {
+                    start: &start.to_string(),
+                    minutes: slots.minutes,
+                })
+                .await?
+            }
+            None => self.confirm(Msg::SlotsRemoved).await?,
+        }
+
+        Ok(())
+    }
+
+    pub async fn calendar(self) -> error::Result<()> {
+        let reply_queue = self.get_reply_to_queue()?;
+        let slots = match reply_queue.slots() {
+            Some(slots) => slots,
+            None => {
+                self.reply(Msg::NoSlots.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+
+        let queue_elems = self.repo.get_elements_for_queue(&reply_queue.key())?;
+        let ics = slots::calendar(&reply_queue, &slots, &queue_elems, Utc::now());
+
+        self.cx
+            .requester
+            .send_document(
+                self.chat.id,
+                InputFile::memory("queue.ics", ics.into_bytes()),
+            )
+            .reply_to_message_id(self.cx.update.id)
+            .disable_notification(self.settings.silent)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn sheet(mut self, arg: Option<String>) -> error::Result<()> {
+        let (name, labels) = match parse_sheet(arg.as_deref().unwrap_or_default()) {
+            Some(sheet) => sheet,
+            None => {
+                let usage = Msg::SheetUsage {
+                    max: MAX_SHEET_SLOTS,
+                };
+                self.reply(usage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsSheet.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let (queue, slots) = self.repo.create_sheet(self.chat.id, name, labels)?;
+        post_queue(
+            &self.cx.requester,
+            &mut self.repo,
+            &self.settings,
+            &queue,
+            &slots,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn claim(mut self, place: i32) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_mutable_queue().await?;
+        if !reply_queue.sheet {
+            self.reply(Msg::NotASheet.text(self.lang)).send().await?;
+            return Ok(());
+        }
+        let (user_id, user_name) = match self.cx.update.from() {
+            Some(user) => (user.id, user.full_name()),
+            None => return Ok(()),
+        };
+
+        // claims don't move slots, so the places the user saw are still right
+        let res = self.repo.claim_slot(
+            &reply_queue.key(),
+            da::ElementRef::Place(place),
+            user_id,
+            user_name,
+        );
+        let text = match res {
+            Ok(_) => {
+                self.update_queue_messages(&reply_queue.key()).await?;
+                self.confirm(Msg::SlotClaimed).await?;
+                return Ok(());
+            }
+            Err(da::Error::SlotTaken) => Msg::SlotTaken,
+            Err(da::Error::AlreadyClaimed) => Msg::AlreadyClaimed,
+            Err(e) => return Err(e.into()),
+        };
+        self.reply(text.text(self.lang)).send().await?;
+        Ok(())
+    }
+
+    pub async fn unclaim(mut self) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_mutable_queue().await?;
+        if !reply_queue.sheet {
+            self.reply(Msg::NotASheet.text(self.lang)).send().await?;
+            return Ok(());
+        }
+        let user_id = match self.cx.update.from() {
+            Some(user) => user.id,
+            None => return Ok(()),
+        };
+
+        match self.repo.release_slot(&reply_queue.key(), user_id)? {
+            Some(_) => {
+                self.update_queue_messages(&reply_queue.key()).await?;
+                self.confirm(Msg::SlotReleased).await?;
+            }
+            None => {
+                self.reply(Msg::NoSlotClaimed.text(self.lang))
+                    .send()
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn assign(mut self, arg: Option<String>) -> error::Result<()> {
+        let arg = arg.unwrap_or_default();
+        // topics can be written on the lines after the command
+        let (options, lines) = arg.split_once('\n').unwrap_or((&arg, ""));
+        let (options, list) = split_roster(options);
+        let args = match assign::parse(options) {
+            Some(args) => args,
+            None => {
+                self.reply(Msg::AssignUsage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        let people_queue = self.get_reply_to_queue()?;
+
+        let topics = match list {
+            Some(name) => match self.named_topics(name)? {
+                Some(topics) => topics,
+                None => {
+                    self.reply(Msg::NoTopics { name }.text(self.lang))
+                        .send()
+                        .await?;
+                    return Ok(());
+                }
+            },
+            None => nonempty_lines(lines).map(str::to_string).collect(),
+        };
+        let topic_count = topics.len();
+        let people = self.repo.get_people_of_queue(&people_queue)?;
+        if people.is_empty() || topics.is_empty() {
+            self.reply(Msg::AssignUsage.text(self.lang)).send().await?;
+            return Ok(());
+        }
+        let assigned = match assign::assign(people.len(), topics, &args) {
+            Some(assigned) => assigned,
+            None => {
+                let text = Msg::NotEnoughTopics {
+                    people: people.len(),
+                    topics: topic_count,
+                };
+                self.reply(text.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+
+        let elements = people
+            .into_iter()
+            .zip(assigned.into_iter().map(Some))
+            .collect();
+        let queue = self.repo.create_new_queue(da::NewQueue {
+            chat_id: self.chat.id,
+            qname: args.name.or(people_queue.qname),
+        })?;
+        let queue_elems = self.repo.insert_labelled_queue(queue.key(), elements)?;
+        post_queue(
+            &self.cx.requester,
+            &mut self.repo,
+            &self.settings,
+            &queue,
+            &queue_elems,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn groups(mut self, arg: Option<String>) -> error::Result<()> {
+        let arg = arg.unwrap_or_default();
+        // constraints can be written on the lines after the command
+        let (options, lines) = arg.split_once('\n').unwrap_or((&arg, ""));
+        let parsed = groups::parse(options).zip(groups::parse_constraints(nonempty_lines(lines)));
+        let (args, constraints) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                self.reply(Msg::GroupsUsage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        let people_queue = self.get_reply_to_queue()?;
+
+        let people = self.repo.get_people_of_queue(&people_queue)?;
+        if people.is_empty() {
+            self.reply(Msg::GroupsUsage.text(self.lang)).send().await?;
+            return Ok(());
+        }
+        let count = args.count.groups(people.len());
+        if args.save && count > MAX_SAVED_GROUPS {
+            let text = Msg::TooManyGroups {
+                max: MAX_SAVED_GROUPS,
+            };
+            self.reply(text.text(self.lang)).send().await?;
+            return Ok(());
+        }
+
+        let names = people
+            .iter()
+            .map(|x| x.element_name.clone())
+            .collect::<Vec<_>>();
+        let teams = match groups::split(&names, count, &constraints, args.balance) {
+            Ok(teams) => teams,
+            Err(e) => {
+                let text = match &e {
+                    groups::SplitError::Unknown(name) => Msg::UnknownMember { name },
+                    groups::SplitError::Conflict(name1, name2) => {
+                        Msg::GroupsConflict { name1, name2 }
+                    }
+                    groups::SplitError::Impossible => Msg::GroupsImpossible,
+                };
+                self.reply(text.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        let teams = teams
+            .into_iter()
+            .map(|team| team.into_iter().map(|i| people[i].clone()).collect())
+            .collect::<Vec<Vec<_>>>();
+
+        if !args.save {
+            self.reply(render::render_groups(&teams, self.lang))
+                .parse_mode(ParseMode::Html)
+                .send()
+                .await?;
+            return Ok(());
+        }
+        for (i, team) in teams.into_iter().enumerate() {
+            let team_name = Msg::Team { number: i + 1 }.text(self.lang);
+            let qname = match &people_queue.qname {
+                Some(qname) => format!("{} — {}", qname, team_name),
+                None => team_name,
+            };
+            let new_queue = da::NewQueue {
+                chat_id: self.chat.id,
+                qname: Some(qname),
+            };
+            post_new_queue(
+                &self.cx.requester,
+                &mut self.repo,
+                &self.settings,
+                new_queue,
+                team,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn pairs(mut self, arg: Option<String>) -> error::Result<()> {
+        match arg.as_deref().map(str::trim) {
+            None | Some("") => {}
+            Some("reset") => {
+                if !self.is_chat_admin().await? {
+                    self.reply(Msg::OnlyAdminsPairs.text(self.lang))
+                        .send()
+                        .await?;
+                    return Ok(());
+                }
+                self.repo.clear_pair_history(self.chat.id)?;
+                self.confirm(Msg::PairsReset).await?;
+                return Ok(());
+            }
+            Some(_) => {
+                self.reply(Msg::PairsUsage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        }
+        let people_queue = self.get_reply_to_queue()?;
+
+        let mut seen = HashSet::new();
+        let people = self
+            .repo
+            .get_people_of_queue(&people_queue)?
+            .into_iter()
+            // somebody in the queue twice can't be paired with themselves
+            .filter(|x| seen.insert(pairs::person_key(x)))
+            .collect::<Vec<_>>();
+        if people.len() < 2 {
+            self.reply(Msg::PairsUsage.text(self.lang)).send().await?;
+            return Ok(());
+        }
+
+        let keys = people.iter().map(pairs::person_key).collect::<Vec<_>>();
+        let history = self
+            .repo
+            .get_pair_history(self.chat.id)?
+            .into_iter()
+            .map(|(first, second, times)| ((first, second), times))
+            .collect();
+        let round = pairs::next_round(&keys, &history);
+        let met = round
+            .iter()
+            .map(|(a, b)| {
+                // byes are counted too, so they go round
+                let b = b.map_or(pairs::BYE_KEY, |b| &keys[b]);
+                let (first, second) = pairs::ordered(&keys[*a], b);
+                (first.to_string(), second.to_string())
+            })
+            .collect::<Vec<_>>();
+        self.repo.record_pairs(self.chat.id, &met)?;
+
+        self.reply(render::render_pairs(&people, &round, self.lang))
+            .parse_mode(ParseMode::Html)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn santa(mut self, arg: Option<String>) -> error::Result<()> {
+        let arg = arg.unwrap_or_default();
+        // a note for everyone goes on the first line, exclusions on the next ones
+        let (note, lines) = arg.split_once('\n').unwrap_or((&arg, ""));
+        let note = note.trim();
+        let exclusions = match santa::parse_exclusions(nonempty_lines(lines)) {
+            Some(exclusions) => exclusions,
+            None => {
+                self.reply(Msg::SantaUsage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsSanta.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+        let people_queue = self.get_reply_to_queue()?;
+
+        let mut seen = HashSet::new();
+        let people = self
+            .repo
+            .get_people_of_queue(&people_queue)?
+            .into_iter()
+            // somebody in the queue twice would get two presents to give
+            .filter(|x| x.user_id.is_none_or(|user_id| seen.insert(user_id)))
+            .collect::<Vec<_>>();
+        if people.len() < 2 {
+            self.reply(Msg::SantaUsage.text(self.lang)).send().await?;
+            return Ok(());
+        }
+
+        let unlinked = people
+            .iter()
+            .filter(|x| x.user_id.is_none())
+            .map(|x| x.element_name.as_str())
+            .collect::<Vec<_>>();
+        if !unlinked.is_empty() {
+            let text = Msg::SantaUnlinked {
+                names: &unlinked.join(", "),
+            };
+            self.reply(text.text(self.lang)).send().await?;
+            return Ok(());
+        }
+        // a chat action reaches only the users who started the bot, and nobody sees it,
+        // so nobody gets a present to give before everyone can get one
+        let mut not_started = Vec::new();
+        for person in &people {
+            let user_id = match person.user_id {
+                Some(user_id) => user_id,
+                None => continue,
+            };
+            let res = self
+                .cx
+                .requester
+                .send_chat_action(user_id, ChatAction::Typing)
+                .send()
+                .await;
+            if let Err(e) = res {
+                log::info!("Can't write to {}: {}", user_id, e);
+                not_started.push(person.element_name.as_str());
+            }
+        }
+        if !not_started.is_empty() {
+            let text = Msg::SantaNotStarted {
+                names: &not_started.join(", "),
+            };
+            self.reply(text.text(self.lang)).send().await?;
+            return Ok(());
+        }
+
+        let names = people
+            .iter()
+            .map(|x| x.element_name.clone())
+            .collect::<Vec<_>>();
+        let receivers = match santa::draw(&names, &exclusions) {
+            Ok(receivers) => receivers,
+            Err(e) => {
+                let text = match &e {
+                    santa::DrawError::Unknown(name) => Msg::UnknownMember { name },
+                    santa::DrawError::Impossible => Msg::SantaImpossible,
+                };
+                self.reply(text.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+
+        // only the givers learn the draw, the chat just hears it is done
+        let chat_title = self.cx.update.chat.title().unwrap_or_default();
+        let users = people.iter().filter_map(|x| x.user_id).collect::<Vec<_>>();
+        let languages = self.repo.chosen_languages(&users)?;
+        let mut undelivered = Vec::new();
+        for (giver, receiver) in people.iter().zip(receivers) {
+            let user_id = match giver.user_id {
+                Some(user_id) => user_id,
+                None => continue,
+            };
+            let text = Msg::SantaAssignment {
+                chat: chat_title,
+                receiver: &people[receiver].element_name,
+                note,
+            };
+            // the language the user chose in the private chat, the one of this chat otherwise
+            let dm_lang = languages
+                .get(&user_id)
+                .copied()
+                .unwrap_or_else(|| i18n::chat_language(&self.settings));
+            let res = self
+                .cx
+                .requester
+                .send_message(user_id, text.text(dm_lang))
+                .send()
+                .await;
+            if let Err(e) = res {
+                log::error!("Couldn't send the draw to {}: {}", user_id, e);
+                undelivered.push(giver.element_name.as_str());
+            }
+        }
+
+        let mut text = Msg::SantaSent {
+            count: people.len() - undelivered.len(),
+        }
+        .text(self.lang);
+        if !undelivered.is_empty() {
+            let names = undelivered.join(", ");
+            text.push('\n');
+            text.push_str(&Msg::SantaNotDelivered { names: &names }.text(self.lang));
+        }
+        self.reply(text).send().await?;
+        Ok(())
+    }
+
+    pub async fn next(mut self) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_mutable_queue().await?;
+
+        let current = self.repo.advance_queue(&reply_queue.key())?;
+
+        self.update_queue_messages(&reply_queue.key()).await?;
+
+        let text = turn_text(current.as_ref(), self.lang);
+        self.reply(text).parse_mode(ParseMode::Html).send().await?;
+
+        Ok(())
+    }
+
+    pub async fn skip(mut self, places: Option<i32>) -> error::Result<()> {
+        if places.is_some_and(|x| x < 1) {
+            self.reply(Msg::PlacesUsage.text(self.lang)).send().await?;
+            return Ok(());
+        }
+        let (reply_queue, _guard) = self.lock_ordered_queue().await?;
+
+        match self.repo.skip_current(&reply_queue.key(), places)? {
+            Some((skipped, current)) => {
+                self.update_queue_messages(&reply_queue.key()).await?;
+                self.reply_skipped(&skipped, places, current.as_ref())
+                    .await?;
+            }
+            None => {
+                self.reply(Msg::NobodyCurrent.text(self.lang))
+                    .send()
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn postpone(mut self, places: Option<i32>) -> error::Result<()> {
+        if places.is_some_and(|x| x < 1) {
+            self.reply(Msg::PlacesUsage.text(self.lang)).send().await?;
+            return Ok(());
+        }
+        let (reply_queue, _guard) = self.lock_ordered_queue().await?;
+        let key = reply_queue.key();
+
+        let own = match self.own_element(&key)? {
+            Some(own) => own,
+            None => {
+                self.reply(Msg::NotInQueue.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+
+        // postponing your own turn is skipping it
+        if own.status == da::ElementStatus::Current {
+            if let Some((skipped, current)) = self.repo.skip_current(&key, places)? {
+                self.update_queue_messages(&key).await?;
+                self.reply_skipped(&skipped, places, current.as_ref())
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let moved = self.repo.move_back(&key, own.id, places)?;
+        self.update_queue_messages(&key).await?;
+        self.confirm(Msg::MovedBack {
+            name: &moved.element_name,
+            places,
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn absent(mut self, place: Option<i32>) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_mutable_queue().await?;
+        let key = reply_queue.key();
+
+        let elem = match place {
+            Some(place) => {
+                self.check_version(&reply_queue)?;
+                da::ElementRef::Place(place)
+            }
+            None => match self.own_element(&key)? {
+                Some(own) => da::ElementRef::Id(own.id),
+                None => {
+                    self.reply(Msg::NotInQueue.text(self.lang)).send().await?;
+                    return Ok(());
+                }
+            },
+        };
+
+        let updated = self.repo.toggle_absent(&key, elem)?;
+        self.update_queue_messages(&key).await?;
+
+        let name = &updated.element_name;
+        self.confirm(if updated.status == da::ElementStatus::Absent {
+            Msg::MarkedAbsent { name }
+        } else {
+            Msg::MarkedPresent { name }
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn settings(self) -> error::Result<()> {
+        let keyboard = settings::settings_keyboard(&self.settings, self.lang);
+        self.reply(Msg::SettingsTitle.text(self.lang))
+            .reply_markup(keyboard)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list(self) -> error::Result<()> {
+        let listing = queue_list(&self.repo, &self.cx.update.chat, self.lang, 0)?;
+        self.reply(listing.text)
+            .parse_mode(ParseMode::Html)
+            .disable_web_page_preview(true)
+            .reply_markup(listing.keyboard)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn archive(mut self) -> error::Result<()> {
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsArchive.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let (reply_queue, _guard) = self.lock_reply_queue().await?;
+        let text = if reply_queue.archived {
+            Msg::AlreadyArchived
+        } else {
+            self.repo.archive_queue(&reply_queue.key())?;
+            Msg::Archived
+        };
+
+        self.reply(text.text(self.lang)).send().await?;
+        Ok(())
+    }
+
+    pub async fn delete(mut self) -> error::Result<()> {
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsDelete.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let (reply_queue, _guard) = self.lock_reply_queue().await?;
+
+        // the command is run again once the deletion is confirmed,
+        // and asks again if the queue changed in the meantime
+        if !is_confirmed(self.confirmed, &reply_queue) {
+            let count = self.repo.get_elements_for_queue(&reply_queue.key())?.len();
+            let keyboard = confirmation_keyboard(
+                Msg::DeleteButton,
+                reply_queue.id,
+                reply_queue.version,
+                self.lang,
+            );
+            self.reply(Msg::ConfirmDeletion { count }.text(self.lang))
+                .reply_markup(keyboard)
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let messages = self.repo.get_queue_messages(&reply_queue.key())?;
+        self.repo.delete_queue(&reply_queue.key())?;
+        unpin_queue_messages(&self.cx.requester, &messages).await;
+
+        self.reply(Msg::Deleted.text(self.lang)).send().await?;
+        Ok(())
+    }
+
+    pub async fn schedule(mut self, arg: Option<String>) -> error::Result<()> {
+        let arg = match arg {
+            Some(arg) => arg,
+            None => {
+                let schedules = self.repo.list_schedules(self.chat.id)?;
+                let listing = render::render_schedule_list(&schedules, self.lang);
+                self.reply(listing.text)
+                    .parse_mode(ParseMode::Html)
+                    .reply_markup(listing.keyboard)
+                    .send()
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsSchedule.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let (weekly, name) = match schedule::parse(&arg) {
+            Some(parsed) => parsed,
+            None => {
+                self.reply(Msg::ScheduleUsage.text(self.lang))
+                    .send()
+                    .await?;
+                return Ok(());
+            }
+        };
+        let (name, roster) = split_roster(name);
+        // a copy of the queue is kept, so deleting the queue doesn't end the schedule
+        let (roster, copied) = match roster {
+            Some(roster) => match self.repo.find_roster(self.chat.id, roster)? {
+                Some(roster) => (roster, false),
+                None => {
+                    return Err(error::Error::NoRoster {
+                        name: roster.to_string(),
+                    })
+                }
+            },
+            None => {
+                let source = self.get_reply_to_queue()?;
+                let people = self.repo.get_people_of_queue(&source)?;
+                let roster_name = schedule::roster_name(name);
+                let roster = self
+                    .repo
+                    .save_new_roster(self.chat.id, &roster_name, people)?;
+                (roster, true)
+            }
+        };
+
+        let scheduled = self.repo.create_schedule(da::NewScheduledQueue {
+            chat_id: self.chat.id,
+            roster_id: roster.id,
+            qname: name.to_string(),
+            next_run_at: schedule::first_run(&weekly, Utc::now()),
+        })?;
+        let name = schedule::queue_name(&scheduled);
+        let next = render::format_time(scheduled.next_run_at);
+        let mut text = Msg::Scheduled {
+            name: &name,
+            next: &next,
+        }
+        .text(self.lang);
+        if copied {
+            let saved = Msg::ScheduleRosterSaved {
+                roster: &roster.name,
+            };
+            text = format!("{} {}", text, saved.text(self.lang));
+        }
+        self.reply(text).send().await?;
+        Ok(())
+    }
+
+    pub async fn signup(self, arg: Option<String>) -> error::Result<()> {
+        let args = match arg.as_deref().and_then(signup::parse) {
+            Some(args) => args,
+            None => {
+                self.reply(Msg::SignupUsage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+
+        let signup = self.repo.create_signup(da::NewSignup {
+            chat_id: self.chat.id,
+            qname: args.name.map(str::to_string),
+            shuffle: args.shuffle,
+            closes_at: Utc::now() + args.duration,
+        })?;
+        let rendered = render::render_signup(&signup, &[], self.lang);
+        let message = self
+            .reply(rendered.text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(rendered.keyboard)
+            .send()
+            .await?;
+        self.repo.set_signup_message(signup.id, message.id as i64)?;
+        Ok(())
+    }
+
+    pub async fn poll(self, arg: Option<String>) -> error::Result<()> {
+        let arg = arg.unwrap_or_default();
+        let mut lines = nonempty_lines(&arg);
+        let question = lines.next().unwrap_or_default();
+        let options = lines.map(str::to_string).collect::<Vec<_>>();
+        if !(MIN_POLL_OPTIONS..=MAX_POLL_OPTIONS).contains(&options.len()) {
+            self.reply(Msg::PollUsage.text(self.lang)).send().await?;
+            return Ok(());
+        }
+
+        // votes are only reported in polls which the bot sent and which aren't anonymous
+        self.cx
+            .requester
+            .send_poll(self.chat.id, question, options, PollType::Regular)
+            .is_anonymous(false)
+            .reply_to_message_id(self.cx.update.id)
+            .disable_notification(self.settings.silent)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn queue_from_poll(mut self, option: Option<String>) -> error::Result<()> {
+        let poll = match self
+            .cx
+            .update
+            .reply_to_message()
+            .and_then(|reply| reply.poll())
+        {
+            Some(poll) => poll.clone(),
+            None => {
+                self.reply(Msg::ReplyToPoll.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        };
+        if poll.is_anonymous {
+            self.reply(Msg::AnonymousPoll.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let options = match option.map(|x| x.parse::<usize>()) {
+            None => (0..poll.options.len()).collect::<Vec<_>>(),
+            Some(Ok(n)) if (1..=poll.options.len()).contains(&n) => vec![n - 1],
+            Some(_) => {
+                let count = poll.options.len();
+                self.reply(Msg::PollOptionUsage { count }.text(self.lang))
+                    .send()
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let mut created = 0;
+        for i in options {
+            let voters = self.repo.poll_voters(&poll.id, i as i32)?;
+            if voters.is_empty() {
+                continue;
+            }
+
+            let new_queue = da::NewQueue {
+                chat_id: self.chat.id,
+                qname: Some(poll.options[i].text.clone()),
+            };
+            post_new_queue(
+                &self.cx.requester,
+                &mut self.repo,
+                &self.settings,
+                new_queue,
+                voters,
+            )
+            .await?;
+            created += 1;
+        }
+
+        if created == 0 {
+            self.reply(Msg::NoVoters.text(self.lang)).send().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn roster(mut self, arg: Option<String>) -> error::Result<()> {
+        let arg = arg.unwrap_or_default();
+        // members can be written on the lines after the command
+        let (command, lines) = arg.split_once('\n').unwrap_or((&arg, ""));
+        let (action, name) = match command.split_once(char::is_whitespace) {
+            Some((action, name)) => (action, name.trim()),
+            None => (command.trim(), ""),
+        };
+
+        match action {
+            "" | "list" => {
+                let rosters = self.repo.list_rosters(self.chat.id)?;
+                let text = render::render_roster_list(&rosters, self.lang);
+                self.reply(text).parse_mode(ParseMode::Html).send().await?;
+                return Ok(());
+            }
+            "show" if !name.is_empty() => {
+                let members = self.roster_members(name)?;
+                let text = render::render_roster(name, &members);
+                self.reply(text).parse_mode(ParseMode::Html).send().await?;
+                return Ok(());
+            }
+            "save" | "edit" | "delete" if !name.is_empty() => {}
+            _ => {
+                self.reply(Msg::RosterUsage.text(self.lang)).send().await?;
+                return Ok(());
+            }
+        }
+
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsRoster.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let text = match action {
+            "save" => match self.new_roster_members(lines).await? {
+                Some(members) => {
+                    let count = members.len();
+                    self.repo.save_roster(self.chat.id, name, members)?;
+                    Msg::RosterSaved { name, count }
+                }
+                None => Msg::RosterSourceUsage,
+            },
+            "edit" if lines.trim().is_empty() => Msg::RosterEditUsage,
+            "edit" => {
+                let mut members = self.roster_members(name)?;
+                for line in nonempty_lines(lines) {
+                    match line.strip_prefix('-') {
+                        Some(removed) => {
+                            let removed = removed.trim();
+                            if let Some(i) = members.iter().position(|x| x.element_name == removed)
+                            {
+                                members.remove(i);
+                            }
+                        }
+                        None => members.push(da::ElementData::named(line.to_string())),
+                    }
+                }
+                let count = members.len();
+                self.repo.save_roster(self.chat.id, name, members)?;
+                Msg::RosterSaved { name, count }
+            }
+            _ => {
+                if !self.repo.delete_roster(self.chat.id, name)? {
+                    return Err(error::Error::NoRoster {
+                        name: name.to_string(),
+                    });
+                }
+                Msg::RosterDeleted
+            }
+        };
+
+        self.reply(text.text(self.lang)).send().await?;
+        Ok(())
+    }
+
+    pub async fn template(mut self, arg: Option<String>) -> error::Result<()> {
+        let arg = match arg {
+            Some(arg) => arg,
+            None => {
+                let template = self.repo.get_template(self.chat.id)?;
+                self.reply(describe_template(&template)).send().await?;
+                return Ok(());
+            }
+        };
+
+        if !self.is_chat_admin().await? {
+            self.reply(Msg::OnlyAdminsTemplate.text(self.lang))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let (option, value) = match arg.split_once(char::is_whitespace) {
+            Some((option, value)) => (option, value.trim()),
+            None => (arg.as_str(), ""),
+        };
+        let text = if option == "reset" {
+            self.repo.reset_template(self.chat.id)?;
+            Msg::TemplateReset.text(self.lang)
+        } else {
+            let mut template = self.repo.get_template(self.chat.id)?;
+            match apply_template_option(&mut template, option, value) {
+                Ok(()) => {
+                    self.repo.update_template(&template)?;
+                    Msg::TemplateUpdated.text(self.lang)
+                }
+                Err(message) => message.text(self.lang),
+            }
+        };
+
+        self.reply(text).send().await?;
+        Ok(())
+    }
+
+    /// A reply to the command, sent as the chat settings say.
+    fn reply<T: Into<String>>(&self, text: T) -> <Bot as Requester>::SendMessage {
+        self.cx
+            .answer(text)
+            .reply_to_message_id(self.cx.update.id)
+            .disable_notification(self.settings.silent)
+    }
+
+    /// Replies about a change made by the command, unless the chat turned these off.
+    async fn confirm(&mut self, message: Msg<'_>) -> error::Result<()> {
+        if self.settings.confirmations {
+            self.reply(message.text(self.lang)).send().await?;
+        }
+        Ok(())
+    }
+
+    /// Replies that the element is moved back and who is called instead.
+    async fn reply_skipped(
+        &mut self,
+        skipped: &da::QueueElement,
+        places: Option<i32>,
+        current: Option<&da::QueueElement>,
+    ) -> error::Result<()> {
+        let moved = Msg::MovedBack {
+            name: &element_mention(skipped),
+            places,
+        };
+        let text = format!(
+            "{}\n{}",
+            moved.text(self.lang),
+            turn_text(current, self.lang)
+        );
+        self.reply(text).parse_mode(ParseMode::Html).send().await?;
+        Ok(())
+    }
+
+    /// The element of the author of the command in the queue, if they are in it.
+    fn own_element(&self, queue: &da::QueueKey) -> error::Result<Option<da::QueueElement>> {
+        match self.cx.update.from() {
+            Some(user) => Ok(self.repo.find_user_element(queue, user.id)?),
+            None => Ok(None),
+        }
+    }
+
+    /// The members of the roster with the name, or the elements of the newest queue with it.
+    fn named_topics(&mut self, name: &str) -> error::Result<Option<Vec<String>>> {
+        let topics = match self.repo.find_roster(self.chat.id, name)? {
+            Some(roster) => self.repo.get_roster_members(roster.id)?,
+            None => match self.repo.find_queue_by_name(self.chat.id, name)? {
+                Some(queue) => self.repo.get_people_of_queue(&queue)?,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(topics.into_iter().map(|x| x.element_name).collect()))
+    }
+
+    fn roster_members(&mut self, name: &str) -> error::Result<Vec<da::ElementData>> {
+        match self.repo.find_roster(self.chat.id, name)? {
+            Some(roster) => Ok(self.repo.get_roster_members(roster.id)?),
+            None => Err(error::Error::NoRoster {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Members for `/roster save`: the lines after the command,
+    /// or else the queue or the file which the command replies to.
+    async fn new_roster_members(
+        &mut self,
+        lines: &str,
+    ) -> error::Result<Option<Vec<da::ElementData>>> {
+        let members = nonempty_lines(lines)
+            .map(|x| da::ElementData::named(x.to_string()))
+            .collect::<Vec<_>>();
+        if !members.is_empty() {
+            return Ok(Some(members));
+        }
+
+        match self.get_reply_to_queue() {
+            Ok(queue) => return Ok(Some(self.repo.get_people_of_queue(&queue)?)),
+            Err(error::Error::NoQueueReply) => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(self.reply_file_text().await?.map(|text| {
+            nonempty_lines(&text)
+                .map(|x| da::ElementData::named(x.to_string()))
+                .collect()
+        }))
+    }
+
+    /// The text of the file which the command replies to.
+    async fn reply_file_text(&mut self) -> error::Result<Option<String>> {
+        let doc = match self
+            .cx
+            .update
+            .reply_to_message()
+            .and_then(|reply| reply.document())
+        {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+
+        let File { file_path, .. } = self
+            .cx
+            .requester
+            .get_file(doc.file_id.clone())
+            .send()
+            .await?;
+        let mut file_data = Vec::new();
+        self.cx
+            .requester
+            .download_file(&file_path, &mut file_data)
+            .await?;
+
+        Ok(Some(from_utf8(file_data.as_slice())?.to_string()))
+    }
+
+    /// Whether the author of the command administers the chat.
+    async fn is_chat_admin(&mut self) -> error::Result<bool> {
+        // anonymous admins write on behalf of the chat itself
+        if self.cx.update.sender_chat().map(|chat| chat.id) == Some(self.chat.id) {
+            return Ok(true);
+        }
+        let user_id = match self.cx.update.from() {
+            Some(user) => user.id,
+            None => return Ok(false),
+        };
+
+        Ok(is_admin(&self.cx.requester, &self.cx.update.chat, user_id).await?)
+    }
+
+    fn get_reply_to_queue(&self) -> error::Result<da::Queue> {
+        if let Some(confirmed) = self.confirmed {
+            let queue = self.repo.get_queue(&da::QueueKey {
+                id: confirmed.queue_id,
+            })?;
+            return if queue.chat_id == self.chat.id {
+                Ok(queue)
+            } else {
+                Err(error::Error::NoQueueReply)
+            };
+        }
 
-    fn get_reply_to_queue(&self) -> error::Result<da::Queue> {
         let reply_queue = self
             .cx
             .update
             .reply_to_message()
-            .map(|Message { id, .. }| {
-                self.repo.queue_exists(da::QueueKey {
-                    id: *id as i64,
-                    chat_id: self.chat.id,
-                })
-            })
+            .map(|Message { id, .. }| self.repo.queue_for_message(self.chat.id, *id as i64))
             .transpose()?
             .flatten();
         match reply_queue {
@@ -472,4 +2687,197 @@ impl CommandHandler<'_> {
             None => Err(error::Error::NoQueueReply),
         }
     }
+
+    /// Resolves the replied-to queue and locks it for the rest of the command.
+    async fn lock_reply_queue(&mut self) -> error::Result<(da::Queue, locks::QueueGuard)> {
+        let reply_queue = self.get_reply_to_queue()?;
+
+        let guard = self.locks.lock(reply_queue.id).await?;
+
+        // the queue could have been changed while waiting for the lock
+        let reply_queue = self.repo.get_queue(&reply_queue.key())?;
+        Ok((reply_queue, guard))
+    }
+
+    /// Like `lock_reply_queue`, but archived queues can't be changed.
+    async fn lock_mutable_queue(&mut self) -> error::Result<(da::Queue, locks::QueueGuard)> {
+        let (reply_queue, guard) = self.lock_reply_queue().await?;
+        if reply_queue.archived {
+            return Err(error::Error::ArchivedQueue);
+        }
+        Ok((reply_queue, guard))
+    }
+
+    /// Like `lock_mutable_queue`, for commands which move elements,
+    /// the slots of a sheet are only claimed and released.
+    async fn lock_ordered_queue(&mut self) -> error::Result<(da::Queue, locks::QueueGuard)> {
+        let (reply_queue, guard) = self.lock_mutable_queue().await?;
+        if reply_queue.sheet {
+            return Err(error::Error::SheetQueue);
+        }
+        Ok((reply_queue, guard))
+    }
+
+    /// Fails if the user saw the queue at an older version than the current one,
+    /// so commands don't act on places which have shifted since.
+    fn check_version(&self, queue: &da::Queue) -> error::Result<()> {
+        let seen_version = match self.confirmed {
+            Some(confirmed) => Some(confirmed.version),
+            None => self.seen_version(queue)?,
+        };
+
+        match seen_version {
+            Some(seen_version) if seen_version < queue.version => {
+                Err(error::Error::OutdatedQueue {
+                    queue_id: queue.id,
+                    version: queue.version,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The version of the queue the replied-to message shows, as recorded when it was
+    /// last sent or edited.
+    fn seen_version(&self, queue: &da::Queue) -> error::Result<Option<i32>> {
+        let reply = match self.cx.update.reply_to_message() {
+            Some(reply) => reply,
+            None => return Ok(None),
+        };
+        let message = match self.repo.get_queue_message(self.chat.id, reply.id as i64)? {
+            Some(message) if message.queue_id == queue.id => message,
+            _ => return Ok(None),
+        };
+        Ok(Some(message.version))
+    }
+
+    pub async fn repost(mut self) -> error::Result<()> {
+        let (reply_queue, _guard) = self.lock_reply_queue().await?;
+
+        let queue = self.repo.get_elements_for_queue(&reply_queue.key())?;
+        self.send_queue(&reply_queue, queue.as_slice(), 0).await?;
+        Ok(())
+    }
+
+    pub async fn show(mut self, arg: Option<String>) -> error::Result<()> {
+        let page = match arg.as_deref() {
+            None => 0,
+            Some("all") => return self.send_queue_document().await,
+            Some(page) => match page.parse::<i32>() {
+                Ok(page) => page - 1,
+                Err(_) => {
+                    self.reply(Msg::PageArgument.text(self.lang)).send().await?;
+                    return Ok(());
+                }
+            },
+        };
+
+        let (reply_queue, _guard) = self.lock_reply_queue().await?;
+
+        let queue = self.repo.get_elements_for_queue(&reply_queue.key())?;
+        self.send_queue(&reply_queue, queue.as_slice(), page)
+            .await?;
+        Ok(())
+    }
+
+    async fn send_queue_document(self) -> error::Result<()> {
+        let reply_queue = self.get_reply_to_queue()?;
+
+        let queue = self.repo.get_elements_for_queue(&reply_queue.key())?;
+        let template = self.repo.get_template(reply_queue.chat_id)?;
+        let str_queue = render::format_queue(&reply_queue, queue.as_slice(), &template, self.lang);
+
+        self.cx
+            .requester
+            .send_document(
+                self.chat.id,
+                InputFile::memory("queue.txt", str_queue.into_bytes()),
+            )
+            .reply_to_message_id(self.cx.update.id)
+            .disable_notification(self.settings.silent)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    /// Sends a new message rendering the queue and remembers it,
+    /// so later modifications are shown there as well.
+    async fn send_queue(
+        &mut self,
+        queue: &da::Queue,
+        queue_elems: &[da::QueueElementForQueue],
+        page: i32,
+    ) -> error::Result<i32> {
+        send_queue(
+            &self.cx.requester,
+            &mut self.repo,
+            queue,
+            queue_elems,
+            self.settings.silent,
+            page,
+        )
+        .await
+    }
+
+    async fn update_queue_messages(&mut self, queue: &da::QueueKey) -> error::Result<()> {
+        update_queue_messages(&self.cx.requester, &mut self.repo, queue).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::str::FromStr;
+    use teloxide::types::InlineKeyboardButtonKind;
+
+    fn queue(id: i64, version: i32) -> da::Queue {
+        da::Queue {
+            id,
+            chat_id: 1,
+            qname: None,
+            version,
+            created_at: Utc::now(),
+            archived: false,
+            updated_at: Utc::now(),
+            starts_at: None,
+            slot_minutes: None,
+            utc_offset: 0,
+            sheet: false,
+        }
+    }
+
+    fn confirm_button(queue: &da::Queue) -> Option<Confirmed> {
+        let keyboard =
+            confirmation_keyboard(Msg::DeleteButton, queue.id, queue.version, da::Language::En);
+        let data = match &keyboard.inline_keyboard[0][0].kind {
+            InlineKeyboardButtonKind::CallbackData(data) => data.clone(),
+            _ => return None,
+        };
+        match callback::CallbackData::from_str(&data) {
+            Ok(callback::CallbackData::Confirm { queue_id, version }) => {
+                Some(Confirmed { queue_id, version })
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn deletion_waits_for_confirmation() {
+        assert!(!is_confirmed(None, &queue(5, 3)));
+    }
+
+    #[test]
+    fn confirm_button_confirms_its_queue() {
+        let confirmed = confirm_button(&queue(5, 3));
+        assert!(confirmed.is_some());
+        assert!(is_confirmed(confirmed, &queue(5, 3)));
+        assert!(!is_confirmed(confirmed, &queue(6, 3)));
+    }
+
+    #[test]
+    fn confirmation_is_asked_again_after_changes() {
+        let confirmed = confirm_button(&queue(5, 3));
+        assert!(!is_confirmed(confirmed, &queue(5, 4)));
+    }
 }
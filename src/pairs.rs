@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use crate::da;
+
+/// Stands for the empty seat in the history, sorts after every person key.
+pub const BYE_KEY: &str = "~bye";
+
+/// Who a person is across sessions: the user if there is one, the name otherwise.
+pub fn person_key(person: &da::ElementData) -> String {
+    match person.user_id {
+        Some(user_id) => format!("u{}", user_id),
+        None => format!("n{}", person.element_name.trim().to_lowercase()),
+    }
+}
+
+/// The rounds of the circle method for `count` people, in which everyone meets everyone
+/// once. The first person stays in place and the rest go round them, with an odd count
+/// whoever faces the empty seat gets a bye.
+pub fn rounds(count: usize) -> Vec<Vec<(usize, Option<usize>)>> {
+    let mut seats = (0..count).map(Some).collect::<Vec<_>>();
+    if count % 2 == 1 {
+        seats.push(None);
+    }
+    let n = seats.len();
+    if n < 2 {
+        return Vec::new();
+    }
+
+    let mut rounds = Vec::new();
+    for _ in 0..n - 1 {
+        let round = (0..n / 2)
+            .filter_map(|i| match (seats[i], seats[n - 1 - i]) {
+                (Some(a), b) | (b, Some(a)) => Some((a, b)),
+                (None, None) => None,
+            })
+            .collect();
+        rounds.push(round);
+        seats[1..].rotate_right(1);
+    }
+    rounds
+}
+
+/// Pairs the people, given by their keys, for the next session. Of the circle method rounds
+/// the one whose pairs met the least is taken, so nobody meets again before everyone met,
+/// and of those the one giving the bye to whoever had the fewest.
+/// Pairs hold indices into `keys`, the second is `None` for a bye.
+pub fn next_round(
+    keys: &[String],
+    history: &HashMap<(String, String), i32>,
+) -> Vec<(usize, Option<usize>)> {
+    // the same people always get the same seats
+    let mut order = (0..keys.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| keys[*a].cmp(&keys[*b]));
+
+    let met = |a: usize, b: Option<usize>| {
+        let b = b.map_or(BYE_KEY, |b| &keys[b]);
+        let pair = ordered(&keys[a], b);
+        history
+            .get(&(pair.0.to_string(), pair.1.to_string()))
+            .copied()
+            .unwrap_or(0)
+    };
+    rounds(keys.len())
+        .into_iter()
+        .map(|round| {
+            round
+                .into_iter()
+                .map(|(a, b)| (order[a], b.map(|b| order[b])))
+                .collect::<Vec<_>>()
+        })
+        .min_by_key(|round| {
+            let pairs = round
+                .iter()
+                .filter(|(_, b)| b.is_some())
+                .map(|(a, b)| met(*a, *b))
+                .sum::<i32>();
+            let byes = round
+                .iter()
+                .filter(|(_, b)| b.is_none())
+                .map(|(a, b)| met(*a, *b))
+                .sum::<i32>();
+            (pairs, byes)
+        })
+        .unwrap_or_default()
+}
+
+/// The keys of a pair in the order they are stored in.
+pub fn ordered<'a>(a: &'a str, b: &'a str) -> (&'a str, &'a str) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn keys(count: usize) -> Vec<String> {
+        (0..count).map(|x| format!("n{}", x)).collect()
+    }
+
+    fn meet(
+        history: &mut HashMap<(String, String), i32>,
+        keys: &[String],
+        a: usize,
+        b: Option<usize>,
+    ) {
+        let b = b.map_or(BYE_KEY, |b| &keys[b]);
+        let (first, second) = ordered(&keys[a], b);
+        *history
+            .entry((first.to_string(), second.to_string()))
+            .or_default() += 1;
+    }
+
+    #[test]
+    fn everyone_meets_everyone_once() {
+        for count in (2..=10).step_by(2) {
+            let rounds = rounds(count);
+            assert_eq!(rounds.len(), count - 1);
+
+            let mut met = HashSet::new();
+            for round in &rounds {
+                assert_eq!(round.len(), count / 2);
+                for (a, b) in round {
+                    let b = b.unwrap();
+                    assert!(met.insert((*a.min(&b), *a.max(&b))));
+                }
+            }
+            assert_eq!(met.len(), count * (count - 1) / 2);
+        }
+    }
+
+    #[test]
+    fn odd_counts_give_one_bye_per_round() {
+        for count in (3..=9).step_by(2) {
+            let rounds = rounds(count);
+            assert_eq!(rounds.len(), count);
+
+            let mut byes = vec![0; count];
+            for round in &rounds {
+                let round_byes = round
+                    .iter()
+                    .filter(|(_, b)| b.is_none())
+                    .collect::<Vec<_>>();
+                assert_eq!(round_byes.len(), 1);
+                byes[round_byes[0].0] += 1;
+            }
+            assert_eq!(byes, vec![1; count]);
+        }
+    }
+
+    #[test]
+    fn nobody_to_pair() {
+        assert!(rounds(0).is_empty());
+        assert_eq!(rounds(1), vec![vec![(0, None)]]);
+    }
+
+    #[test]
+    fn next_round_takes_the_pairs_which_met_least() {
+        let keys = keys(6);
+        let mut history = HashMap::new();
+        let mut seen = HashSet::new();
+        for _ in 0..5 {
+            let round = next_round(&keys, &history);
+            assert_eq!(round.len(), 3);
+            for (a, b) in round {
+                let b = b.unwrap();
+                assert!(seen.insert((a.min(b), a.max(b))));
+                meet(&mut history, &keys, a, Some(b));
+            }
+        }
+        assert_eq!(seen.len(), 15);
+    }
+
+    #[test]
+    fn next_round_gives_the_bye_to_whoever_had_fewest() {
+        let keys = keys(5);
+        let mut history = HashMap::new();
+        // a whole cycle has passed, but 3 got no bye when they joined late
+        for a in 0..5 {
+            for b in a + 1..5 {
+                meet(&mut history, &keys, a, Some(b));
+            }
+            if a != 3 {
+                meet(&mut history, &keys, a, None);
+            }
+        }
+
+        let round = next_round(&keys, &history);
+        assert!(round.contains(&(3, None)));
+    }
+}
@@ -0,0 +1,758 @@
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+use teloxide::{
+    types::{Chat, InlineKeyboardButton, InlineKeyboardMarkup},
+    utils::html,
+};
+
+use crate::{callback::CallbackData, da, i18n::Msg, schedule, slots};
+
+/// Telegram doesn't accept longer messages, the length is counted in UTF-16 code units.
+const MESSAGE_LIMIT: usize = 4096;
+/// Shown in place of the name in a free slot of a sheet.
+const FREE_SLOT: &str = "—";
+/// Free slots of a sheet with a button, the rest are claimed with `/claim`.
+const MAX_CLAIM_BUTTONS: usize = 50;
+const CLAIM_BUTTONS_PER_ROW: usize = 5;
+/// Room kept for the line with the version and the page numbers.
+const FOOTER_RESERVE: usize = 64;
+/// Names and labels of elements are cut to this length, in UTF-16 code units,
+/// so that a page always has room for an element.
+const MAX_NAME_LEN: usize = 256;
+/// The header and footer lines are cut to this length, in UTF-16 code units.
+const MAX_HEADER_LEN: usize = 512;
+/// Longest status mark of a template, in characters.
+pub const MAX_MARK_LEN: usize = 8;
+/// Longest header or footer of a template, in characters.
+pub const MAX_TEMPLATE_TEXT_LEN: usize = 256;
+
+/// One page of a queue in HTML, ready to be sent or edited in.
+pub struct RenderedQueue {
+    pub text: String,
+    pub keyboard: Option<InlineKeyboardMarkup>,
+    /// The page which was actually rendered, requested pages past the end are clamped.
+    pub page: i32,
+}
+
+pub fn render_queue(
+    queue: &da::Queue,
+    queue_elems: &[da::QueueElementForQueue],
+    template: &da::QueueTemplate,
+    lang: da::Language,
+    page: i32,
+) -> RenderedQueue {
+    let header = header(queue, template).map(|x| truncate(&x, MAX_HEADER_LEN).into_owned());
+    let footer = footer(queue, queue_elems, template, lang)
+        .into_iter()
+        .map(|x| truncate(&x, MAX_HEADER_LEN).into_owned())
+        .collect::<Vec<_>>();
+    let lines = queue_elems
+        .iter()
+        .map(|x| element_line(x, queue, template))
+        .collect::<Vec<_>>();
+
+    let budget = (MESSAGE_LIMIT - FOOTER_RESERVE)
+        .saturating_sub(header.as_ref().map_or(0, |x| text_len(x) + 1))
+        .saturating_sub(footer.iter().map(|x| text_len(x) + 1).sum());
+    let pages = paginate(&lines, budget);
+    let page = page.clamp(0, pages.len() as i32 - 1);
+
+    let mut text = String::new();
+    if let Some(header) = header {
+        text.push_str(&html::bold(&html::escape(&header)));
+        text.push('\n');
+    }
+    let page_lines = pages[page as usize].iter().map(|x| x.html.as_str());
+    text.push_str(&page_lines.collect::<Vec<_>>().join("\n"));
+    text.push('\n');
+    for line in footer {
+        text.push('\n');
+        text.push_str(&html::escape(&line));
+    }
+    text.push_str(&format!("\nv{}", queue.version));
+    if pages.len() > 1 {
+        let page_of = Msg::PageOf {
+            page: page + 1,
+            pages: pages.len(),
+        };
+        text.push_str(&format!(" · {}", page_of.text(lang)));
+    }
+
+    let page_keyboard = page_keyboard(queue.id, page, pages.len() as i32, lang);
+    let keyboard = if queue.sheet {
+        Some(sheet_keyboard(queue, queue_elems, page_keyboard, lang))
+    } else {
+        page_keyboard
+    };
+    RenderedQueue {
+        text,
+        keyboard,
+        page,
+    }
+}
+
+/// The whole queue as plain text without the version, for a document.
+pub fn format_queue(
+    queue: &da::Queue,
+    queue_elems: &[da::QueueElementForQueue],
+    template: &da::QueueTemplate,
+    lang: da::Language,
+) -> String {
+    let mut lines = Vec::new();
+    lines.extend(header(queue, template));
+    lines.extend(
+        queue_elems
+            .iter()
+            .map(|x| element_prefix(x, queue, template) + &element_text(x, queue)),
+    );
+    let footer = footer(queue, queue_elems, template, lang);
+    if !footer.is_empty() {
+        lines.push(String::new());
+        lines.extend(footer);
+    }
+    lines.join("\n")
+}
+
+/// Queues shown on one page of `/list`.
+pub const LIST_PAGE_SIZE: i64 = 10;
+
+/// One page of `/list` in HTML.
+pub struct RenderedList {
+    pub text: String,
+    pub keyboard: InlineKeyboardMarkup,
+}
+
+/// Renders listed queues, `page` must already be within `total`.
+pub fn render_queue_list(
+    chat: &Chat,
+    queues: &[da::QueueSummary],
+    total: i64,
+    page: i64,
+    lang: da::Language,
+) -> RenderedList {
+    if queues.is_empty() {
+        return RenderedList {
+            text: Msg::NoQueues.text(lang),
+            keyboard: InlineKeyboardMarkup::default(),
+        };
+    }
+
+    let mut text = html::bold(&Msg::ListTitle { total }.text(lang));
+    let mut keyboard = InlineKeyboardMarkup::default();
+    for (i, summary) in queues.iter().enumerate() {
+        let n = page * LIST_PAGE_SIZE + i as i64 + 1;
+        let queue = &summary.queue;
+        let name = match queue.qname.as_deref() {
+            Some(qname) => html::escape(qname),
+            None => Msg::UnnamedQueue.text(lang),
+        };
+        let link = summary
+            .message_id
+            .and_then(|message_id| message_link(chat, message_id));
+        let created = format_time(queue.created_at);
+        let entry = Msg::ListEntry {
+            count: summary.element_count,
+            created: &created,
+        };
+
+        text.push_str(&format!(
+            "\n{}. {} · {}",
+            n,
+            match &link {
+                Some(link) => html::link(link, &name),
+                None => name,
+            },
+            entry.text(lang)
+        ));
+
+        let mut row = Vec::new();
+        if let Some(link) = link {
+            row.push(InlineKeyboardButton::url(
+                Msg::OpenButton { n }.text(lang),
+                link,
+            ));
+        }
+        row.push(InlineKeyboardButton::callback(
+            Msg::RepostButton { n }.text(lang),
+            CallbackData::Repost { queue_id: queue.id }.to_string(),
+        ));
+        row.push(InlineKeyboardButton::callback(
+            Msg::ArchiveButton { n }.text(lang),
+            CallbackData::Archive {
+                queue_id: queue.id,
+                page,
+            }
+            .to_string(),
+        ));
+        keyboard = keyboard.append_row(row);
+    }
+
+    let pages = (total + LIST_PAGE_SIZE - 1) / LIST_PAGE_SIZE;
+    if pages > 1 {
+        let page_of = Msg::PageOf {
+            page: page as i32 + 1,
+            pages: pages as usize,
+        };
+        text.push_str(&format!("\n\n{}", page_of.text(lang)));
+
+        let mut row = Vec::new();
+        if page > 0 {
+            row.push(InlineKeyboardButton::callback(
+                Msg::PrevPage.text(lang),
+                CallbackData::List { page: page - 1 }.to_string(),
+            ));
+        }
+        if page < pages - 1 {
+            row.push(InlineKeyboardButton::callback(
+                Msg::NextPage.text(lang),
+                CallbackData::List { page: page + 1 }.to_string(),
+            ));
+        }
+        keyboard = keyboard.append_row(row);
+    }
+
+    RenderedList { text, keyboard }
+}
+
+/// Renders the schedules of a chat listed by `/schedule`.
+pub fn render_schedule_list(schedules: &[da::ScheduledQueue], lang: da::Language) -> RenderedList {
+    if schedules.is_empty() {
+        return RenderedList {
+            text: Msg::NoSchedules.text(lang),
+            keyboard: InlineKeyboardMarkup::default(),
+        };
+    }
+
+    let mut text = html::bold(&Msg::SchedulesTitle.text(lang));
+    let mut keyboard = InlineKeyboardMarkup::default();
+    for (i, schedule) in schedules.iter().enumerate() {
+        let n = i + 1;
+        let name = schedule::queue_name(schedule);
+        let next = format_time(schedule.next_run_at);
+        let entry = Msg::ScheduleEntry {
+            name: &name,
+            next: &next,
+        };
+        text.push_str(&format!("\n{}. {}", n, html::escape(&entry.text(lang))));
+
+        keyboard = keyboard.append_row(vec![InlineKeyboardButton::callback(
+            Msg::UnscheduleButton { n }.text(lang),
+            CallbackData::Unschedule {
+                schedule_id: schedule.id,
+            }
+            .to_string(),
+        )]);
+    }
+
+    RenderedList { text, keyboard }
+}
+
+/// The message of `/signup` with the people who signed up so far.
+pub fn render_signup(
+    signup: &da::Signup,
+    entries: &[da::SignupEntry],
+    lang: da::Language,
+) -> RenderedList {
+    let title = Msg::SignupTitle {
+        name: signup.qname.as_deref(),
+    };
+    let closes = format_time(signup.closes_at);
+    let deadline = Msg::SignupDeadline {
+        closes: &closes,
+        shuffle: signup.shuffle,
+    };
+
+    let mut text = html::bold(&html::escape(&title.text(lang)));
+    text.push('\n');
+    text.push_str(&html::escape(&deadline.text(lang)));
+    for (i, entry) in entries.iter().enumerate() {
+        text.push_str(&format!(
+            "\n{}. {}",
+            i + 1,
+            html::escape(&entry.element_name)
+        ));
+    }
+
+    let keyboard = InlineKeyboardMarkup::default().append_row(vec![
+        InlineKeyboardButton::callback(
+            Msg::SignUpButton.text(lang),
+            CallbackData::SignUp {
+                signup_id: signup.id,
+            }
+            .to_string(),
+        ),
+        InlineKeyboardButton::callback(
+            Msg::LeaveSignupButton.text(lang),
+            CallbackData::LeaveSignup {
+                signup_id: signup.id,
+            }
+            .to_string(),
+        ),
+    ]);
+    RenderedList { text, keyboard }
+}
+
+/// Accept and Decline buttons for a request to trade places.
+pub fn swap_request_keyboard(request_id: i64, lang: da::Language) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::default().append_row(vec![
+        InlineKeyboardButton::callback(
+            Msg::AcceptSwapButton.text(lang),
+            CallbackData::AcceptSwap { request_id }.to_string(),
+        ),
+        InlineKeyboardButton::callback(
+            Msg::DeclineSwapButton.text(lang),
+            CallbackData::DeclineSwap { request_id }.to_string(),
+        ),
+    ])
+}
+
+/// The rosters of a chat in HTML, as `/roster list` shows them.
+pub fn render_roster_list(rosters: &[da::RosterSummary], lang: da::Language) -> String {
+    if rosters.is_empty() {
+        return Msg::NoRosters.text(lang);
+    }
+
+    let mut text = html::bold(&Msg::RostersTitle.text(lang));
+    for roster in rosters {
+        let entry = Msg::RosterEntry {
+            name: &roster.name,
+            count: roster.member_count,
+        };
+        text.push_str(&format!("\n• {}", html::escape(&entry.text(lang))));
+    }
+    text
+}
+
+/// The members of a roster in HTML. Users aren't mentioned, so they aren't notified.
+pub fn render_roster(name: &str, members: &[da::ElementData]) -> String {
+    let mut text = html::bold(&html::escape(name));
+    for (i, member) in members.iter().enumerate() {
+        text.push_str(&format!(
+            "\n{}. {}",
+            i + 1,
+            html::escape(&member.element_name)
+        ));
+    }
+    text
+}
+
+/// The teams of `/groups` in HTML, users aren't mentioned.
+pub fn render_groups(teams: &[Vec<da::ElementData>], lang: da::Language) -> String {
+    let mut text = String::new();
+    for (i, team) in teams.iter().enumerate() {
+        if i > 0 {
+            text.push_str("\n\n");
+        }
+        text.push_str(&html::bold(&Msg::Team { number: i + 1 }.text(lang)));
+        for (j, member) in team.iter().enumerate() {
+            text.push_str(&format!(
+                "\n{}. {}",
+                j + 1,
+                html::escape(&member.element_name)
+            ));
+        }
+    }
+    text
+}
+
+/// The pairs of `/pairs` in HTML, the ones without a partner have a bye.
+pub fn render_pairs(
+    people: &[da::ElementData],
+    round: &[(usize, Option<usize>)],
+    lang: da::Language,
+) -> String {
+    let mut text = html::bold(&Msg::PairsTitle.text(lang));
+    let mut n = 0;
+    for (a, b) in round {
+        if let Some(b) = b {
+            n += 1;
+            text.push_str(&format!(
+                "\n{}. {} — {}",
+                n,
+                html::escape(&people[*a].element_name),
+                html::escape(&people[*b].element_name)
+            ));
+        }
+    }
+    for (a, _) in round.iter().filter(|(_, b)| b.is_none()) {
+        let bye = Msg::Bye {
+            name: &people[*a].element_name,
+        };
+        text.push_str(&format!("\n{}", html::escape(&bye.text(lang))));
+    }
+    text
+}
+
+pub fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+/// A t.me link to a message, only public chats and supergroups have them.
+fn message_link(chat: &Chat, message_id: i64) -> Option<String> {
+    if chat.is_private() {
+        return None;
+    }
+    if let Some(username) = chat.username() {
+        return Some(format!("https://t.me/{}/{}", username, message_id));
+    }
+    if !chat.is_supergroup() && !chat.is_channel() {
+        return None;
+    }
+    // private supergroup ids are the link id prefixed with -100
+    let id = chat.id.to_string();
+    let link_id = id.strip_prefix("-100")?;
+    Some(format!("https://t.me/c/{}/{}", link_id, message_id))
+}
+
+struct Line {
+    html: String,
+    /// Length of the text the user sees, markup isn't counted by Telegram.
+    len: usize,
+}
+
+fn element_line(
+    elem: &da::QueueElementForQueue,
+    queue: &da::Queue,
+    template: &da::QueueTemplate,
+) -> Line {
+    let label = elem.label.as_deref().map(|x| truncate(x, MAX_NAME_LEN));
+    let element_name = truncate(&elem.element_name, MAX_NAME_LEN);
+    let mut name = label
+        .as_ref()
+        .map(|label| html::escape(label) + ": ")
+        .unwrap_or_default();
+    let mut visible = label.map(|label| label + ": ").unwrap_or_default();
+    match elem.user_id {
+        Some(user_id) => name.push_str(&html::user_mention(user_id, &element_name)),
+        None if elem.is_free_slot(queue) => name.push_str(FREE_SLOT),
+        None => name.push_str(&html::escape(&element_name)),
+    }
+    visible += if elem.is_free_slot(queue) {
+        FREE_SLOT
+    } else {
+        &element_name
+    };
+    let prefix = element_prefix(elem, queue, template);
+    let text = html::escape(&prefix) + &name;
+    let len = text_len(&prefix) + text_len(&visible);
+
+    let html = match elem.status {
+        da::ElementStatus::Waiting | da::ElementStatus::Skipped => text,
+        da::ElementStatus::Current => html::bold(&text),
+        da::ElementStatus::Done => html::strike(&text),
+        da::ElementStatus::Absent => html::italic(&text),
+    };
+    Line { html, len }
+}
+
+/// The name of an element as the user sees it, with the label of a sheet slot.
+fn element_text(elem: &da::QueueElementForQueue, queue: &da::Queue) -> String {
+    let name = if elem.is_free_slot(queue) {
+        FREE_SLOT
+    } else {
+        &elem.element_name
+    };
+    match &elem.label {
+        Some(label) => format!("{}: {}", label, name),
+        None => name.to_string(),
+    }
+}
+
+/// The status mark, the place and the slot time put before the name of an element.
+fn element_prefix(
+    elem: &da::QueueElementForQueue,
+    queue: &da::Queue,
+    template: &da::QueueTemplate,
+) -> String {
+    let mut prefix = template.mark(elem.status).to_string();
+    if !prefix.is_empty() {
+        prefix.push(' ');
+    }
+    match template.numbering {
+        da::Numbering::Paren => prefix.push_str(&format!("{}) ", elem.queue_place)),
+        da::Numbering::Dot => prefix.push_str(&format!("{}. ", elem.queue_place)),
+        da::Numbering::Hash => prefix.push_str(&format!("#{} ", elem.queue_place)),
+        da::Numbering::Hidden => {}
+    }
+    if let Some(slots) = queue.slots() {
+        let start = slots::slot_start(&slots, elem.queue_place);
+        prefix.push_str(&format!("{} ", start.format("%H:%M")));
+    }
+    prefix
+}
+
+fn header(queue: &da::Queue, template: &da::QueueTemplate) -> Option<String> {
+    fill_placeholders(&template.header, queue)
+}
+
+/// The lines shown between the elements and the version.
+fn footer(
+    queue: &da::Queue,
+    queue_elems: &[da::QueueElementForQueue],
+    template: &da::QueueTemplate,
+    lang: da::Language,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    lines.extend(fill_placeholders(&template.footer, queue));
+    if template.show_counts {
+        let done = queue_elems
+            .iter()
+            .filter(|x| x.status == da::ElementStatus::Done)
+            .count();
+        let counts = Msg::Counts {
+            total: queue_elems.len(),
+            done,
+        };
+        lines.push(counts.text(lang));
+    }
+    lines
+}
+
+/// Replaces `{name}` with the queue name. Empty text, or text naming
+/// a queue which has no name, isn't shown.
+fn fill_placeholders(text: &str, queue: &da::Queue) -> Option<String> {
+    if text.is_empty() {
+        return None;
+    }
+    match (text.contains("{name}"), queue.qname.as_deref()) {
+        (false, _) => Some(text.to_string()),
+        (true, Some(qname)) => Some(text.replace("{name}", qname)),
+        (true, None) => None,
+    }
+}
+
+/// Splits lines into pages which fit into `budget` when joined.
+fn paginate(lines: &[Line], budget: usize) -> Vec<&[Line]> {
+    let mut pages = Vec::new();
+    let mut start = 0;
+    let mut len = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        let line_len = line.len + 1;
+        if i > start && len + line_len > budget {
+            pages.push(&lines[start..i]);
+            start = i;
+            len = 0;
+        }
+        len += line_len;
+    }
+    pages.push(&lines[start..]);
+
+    pages
+}
+
+fn page_keyboard(
+    queue_id: i64,
+    page: i32,
+    pages: i32,
+    lang: da::Language,
+) -> Option<InlineKeyboardMarkup> {
+    if pages <= 1 {
+        return None;
+    }
+
+    let mut buttons = Vec::new();
+    if page > 0 {
+        buttons.push(InlineKeyboardButton::callback(
+            Msg::PrevPage.text(lang),
+            CallbackData::Page {
+                queue_id,
+                page: page - 1,
+            }
+            .to_string(),
+        ));
+    }
+    if page < pages - 1 {
+        buttons.push(InlineKeyboardButton::callback(
+            Msg::NextPage.text(lang),
+            CallbackData::Page {
+                queue_id,
+                page: page + 1,
+            }
+            .to_string(),
+        ));
+    }
+    Some(InlineKeyboardMarkup::default().append_row(buttons))
+}
+
+/// A button for every free slot of a sheet, one to release the own slot and the page buttons.
+fn sheet_keyboard(
+    queue: &da::Queue,
+    queue_elems: &[da::QueueElementForQueue],
+    page_keyboard: Option<InlineKeyboardMarkup>,
+    lang: da::Language,
+) -> InlineKeyboardMarkup {
+    let free = queue_elems
+        .iter()
+        .filter(|x| x.is_free_slot(queue))
+        .take(MAX_CLAIM_BUTTONS)
+        .map(|x| {
+            InlineKeyboardButton::callback(
+                x.queue_place.to_string(),
+                CallbackData::Claim {
+                    queue_id: queue.id,
+                    element_id: x.id,
+                }
+                .to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut keyboard = InlineKeyboardMarkup::default();
+    for row in free.chunks(CLAIM_BUTTONS_PER_ROW) {
+        keyboard = keyboard.append_row(row.to_vec());
+    }
+    keyboard = keyboard.append_row(vec![InlineKeyboardButton::callback(
+        Msg::ReleaseSlotButton.text(lang),
+        CallbackData::Release { queue_id: queue.id }.to_string(),
+    )]);
+    for row in page_keyboard.into_iter().flat_map(|x| x.inline_keyboard) {
+        keyboard = keyboard.append_row(row);
+    }
+    keyboard
+}
+
+fn text_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Cuts the text to `max` UTF-16 code units, ending it with an ellipsis if anything is cut.
+fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    if text_len(text) <= max {
+        return Cow::Borrowed(text);
+    }
+    let mut truncated = String::new();
+    let mut len = 1;
+    for c in text.chars() {
+        if len + c.len_utf16() > max {
+            break;
+        }
+        truncated.push(c);
+        len += c.len_utf16();
+    }
+    truncated.push('…');
+    Cow::Owned(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn line(len: usize) -> Line {
+        Line {
+            html: "x".repeat(len),
+            len,
+        }
+    }
+
+    fn test_queue(qname: Option<String>) -> da::Queue {
+        let created_at = Utc.ymd(2021, 7, 3).and_hms(12, 0, 0);
+        da::Queue {
+            id: 1,
+            chat_id: -1,
+            qname,
+            version: 1,
+            created_at,
+            archived: false,
+            updated_at: created_at,
+            starts_at: None,
+            slot_minutes: None,
+            utc_offset: 0,
+            sheet: false,
+        }
+    }
+
+    fn test_template() -> da::QueueTemplate {
+        da::QueueTemplate {
+            chat_id: -1,
+            numbering: da::Numbering::Dot,
+            waiting_mark: String::new(),
+            current_mark: "▶".to_string(),
+            done_mark: "✓".to_string(),
+            header: "{name}".to_string(),
+            footer: "{name}".to_string(),
+            show_counts: true,
+            skipped_mark: "↷".to_string(),
+            absent_mark: "✗".to_string(),
+        }
+    }
+
+    fn test_elements(names: &[String]) -> Vec<da::QueueElementForQueue> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| da::QueueElementForQueue {
+                element_name: name.clone(),
+                queue_place: i as i32 + 1,
+                id: i as i64 + 1,
+                status: da::ElementStatus::Waiting,
+                user_id: None,
+                label: None,
+            })
+            .collect()
+    }
+
+    fn page_sizes(pages: &[&[Line]]) -> Vec<usize> {
+        pages.iter().map(|x| x.len()).collect()
+    }
+
+    #[test]
+    fn paginate_fills_pages_up_to_the_budget() {
+        // every line takes its length and a line break
+        let lines = (0..4).map(|_| line(9)).collect::<Vec<_>>();
+        assert_eq!(page_sizes(&paginate(&lines, 40)), [4]);
+        assert_eq!(page_sizes(&paginate(&lines, 39)), [3, 1]);
+        assert_eq!(page_sizes(&paginate(&lines, 20)), [2, 2]);
+    }
+
+    #[test]
+    fn paginate_keeps_a_line_over_the_budget_on_its_own_page() {
+        let lines = vec![line(5), line(50), line(5)];
+        assert_eq!(page_sizes(&paginate(&lines, 20)), [1, 1, 1]);
+    }
+
+    #[test]
+    fn paginate_gives_an_empty_queue_one_page() {
+        assert_eq!(page_sizes(&paginate(&[], 20)), [0]);
+    }
+
+    #[test]
+    fn truncate_counts_utf16_and_keeps_characters_whole() {
+        assert_eq!(truncate("short", 5), "short");
+        assert_eq!(truncate("longer", 5), "long…");
+        // each emoji takes two code units
+        assert_eq!(truncate("😀😀😀", 4), "😀…");
+        assert_eq!(text_len(&truncate("😀😀😀", 4)), 3);
+    }
+
+    #[test]
+    fn long_names_fit_into_a_message() {
+        let queue = test_queue(Some("q".repeat(5000)));
+        let names = (0..40)
+            .map(|i| format!("{}{}", i, "é".repeat(5000)))
+            .collect::<Vec<_>>();
+        let elements = test_elements(&names);
+
+        let mut page = 0;
+        loop {
+            let rendered =
+                render_queue(&queue, &elements, &test_template(), da::Language::En, page);
+            assert!(text_len(&rendered.text) <= MESSAGE_LIMIT);
+            if rendered.page < page {
+                break;
+            }
+            page += 1;
+        }
+        assert!(page > 1);
+    }
+
+    #[test]
+    fn only_sheets_have_free_slots() {
+        let elements = test_elements(&[String::new()]);
+        let mut queue = test_queue(None);
+        assert_eq!(element_text(&elements[0], &queue), "");
+        queue.sheet = true;
+        assert_eq!(element_text(&elements[0], &queue), FREE_SLOT);
+    }
+}
@@ -0,0 +1,183 @@
+use rand::seq::SliceRandom;
+
+use crate::groups;
+
+/// Parses lines like `exclude: Ann, Bob`, none of whom gives a present to another.
+pub fn parse_exclusions<'a>(lines: impl Iterator<Item = &'a str>) -> Option<Vec<Vec<String>>> {
+    lines
+        .map(|line| {
+            let (kind, names) = line.split_once(':')?;
+            if kind.trim() != "exclude" {
+                return None;
+            }
+            Some(
+                names
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|x| !x.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DrawError {
+    /// A name of the exclusions isn't among the participants.
+    Unknown(String),
+    /// The exclusions leave somebody without anyone to give a present to.
+    Impossible,
+}
+
+/// Draws who gives a present to whom: `result[giver]` is the receiver, never the giver
+/// themselves nor anyone excluded with them. Returns indices into `names`.
+pub fn draw(names: &[String], exclusions: &[Vec<String>]) -> Result<Vec<usize>, DrawError> {
+    let mut allowed = vec![vec![true; names.len()]; names.len()];
+    for (i, row) in allowed.iter_mut().enumerate() {
+        row[i] = false;
+    }
+    for set in exclusions {
+        let people = set
+            .iter()
+            .map(|name| {
+                groups::find_person(names, name).ok_or_else(|| DrawError::Unknown(name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for a in &people {
+            for b in &people {
+                allowed[*a][*b] = false;
+            }
+        }
+    }
+
+    // a random perfect matching of givers and receivers, which are shuffled for it
+    let mut rng = rand::rngs::OsRng;
+    let mut givers = (0..names.len()).collect::<Vec<_>>();
+    givers.shuffle(&mut rng);
+    let mut receivers = givers.clone();
+    receivers.shuffle(&mut rng);
+
+    let mut giver_of = vec![None; names.len()];
+    for giver in givers {
+        let mut seen = vec![false; names.len()];
+        if !find_receiver(giver, &receivers, &allowed, &mut giver_of, &mut seen) {
+            return Err(DrawError::Impossible);
+        }
+    }
+
+    let mut result = vec![0; names.len()];
+    for (receiver, giver) in giver_of.into_iter().enumerate() {
+        // every giver has found a receiver, so every receiver has a giver
+        result[giver.unwrap_or(receiver)] = receiver;
+    }
+    Ok(result)
+}
+
+/// Finds a receiver for the giver, moving the givers who have one to others if needed.
+fn find_receiver(
+    giver: usize,
+    receivers: &[usize],
+    allowed: &[Vec<bool>],
+    giver_of: &mut [Option<usize>],
+    seen: &mut [bool],
+) -> bool {
+    for receiver in receivers {
+        if seen[*receiver] || !allowed[giver][*receiver] {
+            continue;
+        }
+        seen[*receiver] = true;
+        let free = match giver_of[*receiver] {
+            Some(other) => find_receiver(other, receivers, allowed, giver_of, seen),
+            None => true,
+        };
+        if free {
+            giver_of[*receiver] = Some(giver);
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// Checks that everyone gives one present and gets one, never to themselves.
+    fn assert_valid(result: &[usize]) {
+        let mut receivers = result.to_vec();
+        receivers.sort_unstable();
+        assert_eq!(receivers, (0..result.len()).collect::<Vec<_>>());
+        assert!(result.iter().enumerate().all(|(giver, x)| giver != *x));
+    }
+
+    #[test]
+    fn parses_exclusions() {
+        let lines = ["exclude: Ann, Bob", "exclude:Carl,,Dan "];
+        assert_eq!(
+            parse_exclusions(lines.iter().copied()),
+            Some(vec![people(&["Ann", "Bob"]), people(&["Carl", "Dan"])])
+        );
+        assert_eq!(parse_exclusions(["Ann, Bob"].iter().copied()), None);
+        assert_eq!(parse_exclusions(["apart: Ann"].iter().copied()), None);
+        assert_eq!(parse_exclusions(std::iter::empty()), Some(Vec::new()));
+    }
+
+    #[test]
+    fn nobody_draws_themselves() {
+        for count in 2..10 {
+            let names = (0..count).map(|x| x.to_string()).collect::<Vec<_>>();
+            for _ in 0..10 {
+                assert_valid(&draw(&names, &[]).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn respects_exclusions() {
+        let names = people(&["Ann", "Bob", "Carl", "Dan", "Eve"]);
+        let exclusions = vec![people(&["ann", "Bob"]), people(&["Carl", "Dan"])];
+        for _ in 0..20 {
+            let result = draw(&names, &exclusions).unwrap();
+            assert_valid(&result);
+            assert_ne!(result[0], 1);
+            assert_ne!(result[1], 0);
+            assert_ne!(result[2], 3);
+            assert_ne!(result[3], 2);
+        }
+
+        // only one way is left: Ann and Bob give to each other, so do the rest
+        let exclusions = vec![
+            people(&["Ann", "Carl"]),
+            people(&["Ann", "Dan"]),
+            people(&["Bob", "Carl"]),
+            people(&["Bob", "Dan"]),
+        ];
+        let names = people(&["Ann", "Bob", "Carl", "Dan"]);
+        assert_eq!(draw(&names, &exclusions), Ok(vec![1, 0, 3, 2]));
+    }
+
+    #[test]
+    fn fails_when_over_constrained() {
+        assert_eq!(draw(&people(&["Ann"]), &[]), Err(DrawError::Impossible));
+
+        let names = people(&["Ann", "Bob", "Carl", "Dan"]);
+        // Dan has to give to Ann, Bob and Carl, who can only give to Dan
+        let exclusions = vec![people(&["Ann", "Bob", "Carl"])];
+        assert_eq!(draw(&names, &exclusions), Err(DrawError::Impossible));
+    }
+
+    #[test]
+    fn reports_unknown_people() {
+        let names = people(&["Ann", "Bob"]);
+        let exclusions = vec![people(&["Ann", "Zed"])];
+        assert_eq!(
+            draw(&names, &exclusions),
+            Err(DrawError::Unknown("Zed".to_string()))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveTime, Utc, Weekday};
+use teloxide::prelude::*;
+
+use crate::{da, error};
+
+/// How often schedules are checked for queues to create.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// A time of `/schedule` repeated every week.
+#[derive(Clone, Copy, Debug)]
+pub struct WeeklyTime {
+    weekday: Weekday,
+    time: NaiveTime,
+    offset: FixedOffset,
+}
+
+/// Parses `tue 10:00 Lab {n}` into the time and the queue name. The time is in UTC
+/// unless it ends with an offset like `10:00+03` or `10:00-05:30`.
+pub fn parse(arg: &str) -> Option<(WeeklyTime, &str)> {
+    let mut args = arg.splitn(3, char::is_whitespace);
+    let weekday = args.next()?.parse().ok()?;
+    let (time, offset) = parse_time(args.next()?)?;
+    let name = args.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let weekly = WeeklyTime {
+        weekday,
+        time,
+        offset,
+    };
+    Some((weekly, name))
+}
+
+pub fn parse_time(s: &str) -> Option<(NaiveTime, FixedOffset)> {
+    let (time, offset) = s.split_at(s.find(['+', '-'].as_ref()).unwrap_or(s.len()));
+    let time = NaiveTime::parse_from_str(time, "%H:%M").ok()?;
+    if offset.is_empty() {
+        return Some((time, FixedOffset::east(0)));
+    }
+
+    let (sign, offset) = offset.split_at(1);
+    let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    let seconds = (hours * 60 + minutes) * 60;
+    let offset = if sign == "-" {
+        FixedOffset::west_opt(seconds)?
+    } else {
+        FixedOffset::east_opt(seconds)?
+    };
+    Some((time, offset))
+}
+
+/// The first moment at the weekly time after `now`.
+pub fn first_run(weekly: &WeeklyTime, now: DateTime<Utc>) -> DateTime<Utc> {
+    let local = now.with_timezone(&weekly.offset);
+    let days_ahead = (weekly.weekday.num_days_from_monday() as i64
+        - local.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+    let run = (local.date() + Duration::days(days_ahead)).and_time(weekly.time);
+    // a fixed offset has every local time, the fallback is never used
+    let run = run.map_or(now, |run| run.with_timezone(&Utc));
+    if run <= now {
+        run + Duration::weeks(1)
+    } else {
+        run
+    }
+}
+
+/// The run after the current one, runs missed while the bot was down are skipped.
+fn next_run(schedule: &da::ScheduledQueue, now: DateTime<Utc>) -> DateTime<Utc> {
+    let mut run = schedule.next_run_at + Duration::weeks(1);
+    while run <= now {
+        run = run + Duration::weeks(1);
+    }
+    run
+}
+
+/// The name of the next queue of the schedule.
+pub fn queue_name(schedule: &da::ScheduledQueue) -> String {
+    let number = schedule.next_number.to_string();
+    if schedule.qname.contains("{n}") {
+        schedule.qname.replace("{n}", &number)
+    } else {
+        format!("{} {}", schedule.qname, number)
+    }
+}
+
+/// The name of the roster the people of a scheduled queue are copied to, the queue name
+/// without the number.
+pub fn roster_name(qname: &str) -> String {
+    let name = qname
+        .replace("{n}", " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if name.is_empty() {
+        qname.trim().to_string()
+    } else {
+        name
+    }
+}
+
+/// Checks every minute for schedules which are due and posts their queues.
+pub async fn run(bot: Bot) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = create_due_queues(&bot).await {
+            log::error!("Couldn't create scheduled queues: {}", e);
+        }
+    }
+}
+
+async fn create_due_queues(bot: &Bot) -> error::Result<()> {
+    let due = {
+        let repo = da::QueueRepository::from_connection(crate::establish_connection());
+        repo.due_schedules()?
+    };
+
+    for schedule in due {
+        let schedule_id = schedule.id;
+        if let Err(e) = create_scheduled_queue(bot, schedule).await {
+            log::error!(
+                "Couldn't create the queue of schedule {}: {}",
+                schedule_id,
+                e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Does what `/queuerand` does for the roster of the schedule.
+async fn create_scheduled_queue(bot: &Bot, schedule: da::ScheduledQueue) -> error::Result<()> {
+    let mut repo = da::QueueRepository::from_connection(crate::establish_connection());
+
+    // moved first, so a failing schedule doesn't create a queue every minute
+    if repo
+        .advance_schedule(&schedule, next_run(&schedule, Utc::now()))?
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let mut elements = repo.get_roster_members(schedule.roster_id)?;
+    crate::shuffle_elements(&mut elements);
+
+    let settings = repo.get_settings(schedule.chat_id)?;
+    let new_queue = da::NewQueue {
+        chat_id: schedule.chat_id,
+        qname: Some(queue_name(&schedule)),
+    };
+    log::info!(
+        "Chat: {}; Scheduled queue {:?}",
+        schedule.chat_id,
+        new_queue.qname
+    );
+    crate::post_new_queue(bot, &mut repo, &settings, new_queue, elements).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Tuesday, 2021-10-05.
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, 10, 5).and_hms(hour, minute, 0)
+    }
+
+    fn weekly(arg: &str) -> WeeklyTime {
+        parse(arg).unwrap().0
+    }
+
+    #[test]
+    fn parses_weekday_time_and_name() {
+        let (weekly, name) = parse("tue 10:00 Lab {n}").unwrap();
+        assert_eq!(weekly.weekday, Weekday::Tue);
+        assert_eq!(weekly.time, NaiveTime::from_hms(10, 0, 0));
+        assert_eq!(weekly.offset, FixedOffset::east(0));
+        assert_eq!(name, "Lab {n}");
+
+        assert_eq!(parse("Friday 9:30 x").unwrap().0.weekday, Weekday::Fri);
+        assert!(parse("tue 10:00").is_none());
+        assert!(parse("tue 10:00   ").is_none());
+        assert!(parse("someday 10:00 Lab").is_none());
+        assert!(parse("tue 25:00 Lab").is_none());
+    }
+
+    #[test]
+    fn parses_utc_offsets() {
+        let time = NaiveTime::from_hms(10, 0, 0);
+        assert_eq!(
+            parse_time("10:00+03"),
+            Some((time, FixedOffset::east(3 * 3600)))
+        );
+        assert_eq!(
+            parse_time("10:00-05:30"),
+            Some((time, FixedOffset::west(5 * 3600 + 30 * 60)))
+        );
+        assert!(parse_time("10:00+03:60").is_none());
+        assert!(parse_time("10:00+24").is_none());
+        assert!(parse_time("10:00+").is_none());
+    }
+
+    #[test]
+    fn first_run_is_later_today_if_the_time_is_ahead() {
+        assert_eq!(first_run(&weekly("tue 10:00 x"), at(9, 0)), at(10, 0));
+    }
+
+    #[test]
+    fn first_run_is_next_week_if_the_time_has_passed() {
+        let run = at(10, 0) + Duration::weeks(1);
+        assert_eq!(first_run(&weekly("tue 10:00 x"), at(11, 0)), run);
+        // a run right now is already missed
+        assert_eq!(first_run(&weekly("tue 10:00 x"), at(10, 0)), run);
+    }
+
+    #[test]
+    fn first_run_counts_days_in_the_offset_of_the_time() {
+        assert_eq!(
+            first_run(&weekly("thu 08:00 x"), at(12, 0)),
+            at(8, 0) + Duration::days(2)
+        );
+        // it is already Wednesday 01:00 at +03, so Tuesday is almost a week away
+        assert_eq!(
+            first_run(&weekly("tue 02:00+03 x"), at(22, 0)),
+            at(23, 0) + Duration::days(6)
+        );
+        assert_eq!(first_run(&weekly("wed 02:00+03 x"), at(22, 0)), at(23, 0));
+    }
+
+    #[test]
+    fn roster_names_leave_out_the_number() {
+        assert_eq!(roster_name("Lab {n}"), "Lab");
+        assert_eq!(roster_name("Lab {n} of OS"), "Lab of OS");
+        assert_eq!(roster_name("Lab"), "Lab");
+        assert_eq!(roster_name("{n}"), "{n}");
+    }
+
+    #[test]
+    fn next_run_skips_missed_weeks() {
+        let schedule = da::ScheduledQueue {
+            id: 1,
+            chat_id: -1,
+            qname: "Lab {n}".to_string(),
+            next_number: 3,
+            next_run_at: at(10, 0),
+            roster_id: 1,
+        };
+        assert_eq!(
+            next_run(&schedule, at(10, 0)),
+            at(10, 0) + Duration::weeks(1)
+        );
+        let later = at(10, 0) + Duration::weeks(3);
+        assert_eq!(next_run(&schedule, later), at(10, 0) + Duration::weeks(4));
+        assert_eq!(queue_name(&schedule), "Lab 3");
+    }
+}
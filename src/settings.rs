@@ -0,0 +1,137 @@
+use std::{fmt, str::FromStr};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+use crate::{callback::CallbackData, da, i18n::Msg};
+
+/// A chat setting which can be changed from the `/settings` keyboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Setting {
+    AutoPin,
+    Silent,
+    Confirmations,
+    Shuffle,
+    Language,
+    Expiry,
+    ExpiryAction,
+}
+
+/// Choices of `/settings` for the days after which idle queues expire.
+const EXPIRY_DAYS: [i32; 4] = [7, 30, 90, 180];
+
+impl Setting {
+    const ALL: [Setting; 7] = [
+        Setting::AutoPin,
+        Setting::Silent,
+        Setting::Confirmations,
+        Setting::Shuffle,
+        Setting::Language,
+        Setting::Expiry,
+        Setting::ExpiryAction,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Setting::AutoPin => "pin",
+            Setting::Silent => "silent",
+            Setting::Confirmations => "confirm",
+            Setting::Shuffle => "shuffle",
+            Setting::Language => "lang",
+            Setting::Expiry => "expiry",
+            Setting::ExpiryAction => "expire_action",
+        }
+    }
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Setting {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Setting::ALL
+            .iter()
+            .copied()
+            .find(|setting| setting.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// Moves the setting to its next value.
+pub fn toggle(settings: &mut da::ChatSettings, setting: Setting) {
+    match setting {
+        Setting::AutoPin => settings.auto_pin = !settings.auto_pin,
+        Setting::Silent => settings.silent = !settings.silent,
+        Setting::Confirmations => settings.confirmations = !settings.confirmations,
+        Setting::Shuffle => {
+            settings.shuffle = match settings.shuffle {
+                da::ShuffleMode::Keep => da::ShuffleMode::Shuffle,
+                da::ShuffleMode::Shuffle => da::ShuffleMode::Keep,
+            }
+        }
+        Setting::Language => {
+            settings.language = match settings.language {
+                None => Some(da::Language::En),
+                Some(da::Language::En) => Some(da::Language::Uk),
+                Some(da::Language::Uk) => None,
+            }
+        }
+        Setting::Expiry => {
+            settings.expiry_days = match settings.expiry_days {
+                None => Some(EXPIRY_DAYS[0]),
+                // days set outside of the keyboard start the cycle over
+                Some(days) => match EXPIRY_DAYS.iter().position(|&x| x == days) {
+                    Some(i) => EXPIRY_DAYS.get(i + 1).copied(),
+                    None => Some(EXPIRY_DAYS[0]),
+                },
+            }
+        }
+        Setting::ExpiryAction => {
+            settings.expiry_action = match settings.expiry_action {
+                da::ExpiryAction::Archive => da::ExpiryAction::Delete,
+                da::ExpiryAction::Delete => da::ExpiryAction::Archive,
+            }
+        }
+    }
+}
+
+/// One button per setting showing its current value.
+pub fn settings_keyboard(settings: &da::ChatSettings, lang: da::Language) -> InlineKeyboardMarkup {
+    Setting::ALL
+        .iter()
+        .fold(InlineKeyboardMarkup::default(), |keyboard, &setting| {
+            keyboard.append_row(vec![InlineKeyboardButton::callback(
+                setting_label(settings, setting).text(lang),
+                CallbackData::Setting(setting).to_string(),
+            )])
+        })
+}
+
+fn setting_label(settings: &da::ChatSettings, setting: Setting) -> Msg<'static> {
+    match setting {
+        Setting::AutoPin => Msg::AutoPinSetting {
+            on: settings.auto_pin,
+        },
+        Setting::Silent => Msg::SilentSetting {
+            on: settings.silent,
+        },
+        Setting::Confirmations => Msg::ConfirmationsSetting {
+            on: settings.confirmations,
+        },
+        Setting::Shuffle => Msg::ShuffleSetting {
+            mode: settings.shuffle,
+        },
+        Setting::Language => Msg::LanguageSetting {
+            language: settings.language,
+        },
+        Setting::Expiry => Msg::ExpirySetting {
+            days: settings.expiry_days,
+        },
+        Setting::ExpiryAction => Msg::ExpiryActionSetting {
+            action: settings.expiry_action,
+        },
+    }
+}
@@ -0,0 +1,203 @@
+use chrono::Duration;
+use teloxide::prelude::*;
+
+use crate::{da, error, i18n, i18n::Msg};
+
+/// How often sign-ups are checked for passed deadlines.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+/// Sign-ups can't stay open for longer.
+const MAX_DAYS: i64 = 7;
+
+/// Arguments of `/signup`.
+#[derive(Clone, Copy, Debug)]
+pub struct SignupArgs<'a> {
+    pub name: Option<&'a str>,
+    pub duration: Duration,
+    pub shuffle: bool,
+}
+
+/// Parses `[name] duration [shuffle]`, the duration is like `30s`, `10m`, `2h` or `1d`.
+pub fn parse(arg: &str) -> Option<SignupArgs<'_>> {
+    let (rest, last) = split_last(arg.trim());
+    let (shuffle, (rest, last)) = if last == "shuffle" {
+        (true, split_last(rest))
+    } else {
+        (false, (rest, last))
+    };
+    let duration = parse_duration(last)?;
+
+    Some(SignupArgs {
+        name: Some(rest).filter(|x| !x.is_empty()),
+        duration,
+        shuffle,
+    })
+}
+
+fn split_last(s: &str) -> (&str, &str) {
+    match s.rsplit_once(char::is_whitespace) {
+        Some((rest, last)) => (rest.trim_end(), last),
+        None => ("", s),
+    }
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let unit = s.chars().last()?;
+    let amount: i64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+    let unit_seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+
+    let seconds = amount.checked_mul(unit_seconds)?;
+    if seconds > 0 && seconds <= Duration::days(MAX_DAYS).num_seconds() {
+        Some(Duration::seconds(seconds))
+    } else {
+        None
+    }
+}
+
+/// Checks every 10 s for sign-ups past their deadline and turns them into queues.
+pub async fn run(bot: Bot) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = close_due_signups(&bot).await {
+            log::error!("Couldn't close sign-ups: {}", e);
+        }
+    }
+}
+
+async fn close_due_signups(bot: &Bot) -> error::Result<()> {
+    let due = {
+        let repo = da::QueueRepository::from_connection(crate::establish_connection());
+        repo.due_signups()?
+    };
+
+    for signup in due {
+        if let Err(e) = close_signup(bot, signup.id).await {
+            log::error!("Couldn't close sign-up {}: {}", signup.id, e);
+        }
+    }
+    Ok(())
+}
+
+fn create_queue(
+    repo: &da::QueueRepository,
+    new_queue: da::NewQueue,
+    elements: Vec<da::ElementData>,
+) -> error::Result<(da::Queue, Vec<da::QueueElementForQueue>)> {
+    let queue = repo.create_new_queue(new_queue)?;
+    let queue_elems = repo.insert_filled_queue(queue.key(), elements)?;
+    Ok((queue, queue_elems))
+}
+
+/// Creates the queue of the people who signed up and takes the buttons off the message.
+async fn close_signup(bot: &Bot, signup_id: i64) -> error::Result<()> {
+    let mut repo = da::QueueRepository::from_connection(crate::establish_connection());
+    // closed first, so nobody signs up after the queue is made
+    let signup = match repo.close_signup(signup_id)? {
+        Some(signup) => signup,
+        None => return Ok(()),
+    };
+
+    let settings = repo.get_settings(signup.chat_id)?;
+    let lang = i18n::resolve(settings.language, None);
+    let mut elements = repo
+        .get_signup_entries(signup.id)?
+        .into_iter()
+        .map(da::ElementData::from)
+        .collect::<Vec<_>>();
+    let count = elements.len();
+    if signup.shuffle {
+        crate::shuffle_elements(&mut elements);
+    }
+
+    let created = if count > 0 {
+        let new_queue = da::NewQueue {
+            chat_id: signup.chat_id,
+            qname: signup.qname.clone(),
+        };
+        match create_queue(&repo, new_queue, elements) {
+            Ok(created) => Some(created),
+            Err(e) => {
+                // opened again to be closed on the next check
+                repo.reopen_signup(signup.id)?;
+                return Err(e);
+            }
+        }
+    } else {
+        None
+    };
+    if let Some((queue, queue_elems)) = created {
+        if let Err(e) = crate::post_queue(bot, &mut repo, &settings, &queue, &queue_elems).await {
+            // a queue which was never shown is made and posted again on the next check,
+            // a shown one is kept even if pinning it failed
+            if repo.get_queue_messages(&queue.key())?.is_empty() {
+                repo.delete_queue(&queue.key())?;
+                repo.reopen_signup(signup.id)?;
+            } else {
+                repo.delete_signup(signup.id)?;
+            }
+            return Err(e);
+        }
+    }
+    repo.delete_signup(signup.id)?;
+
+    if let Some(message_id) = signup.message_id {
+        let text = if count > 0 {
+            Msg::SignupClosed {
+                name: signup.qname.as_deref(),
+                count,
+            }
+        } else {
+            Msg::NobodySignedUp
+        };
+        bot.edit_message_text(signup.chat_id, message_id as i32, text.text(lang))
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30s"), Some(Duration::seconds(30)));
+        assert_eq!(parse_duration("10m"), Some(Duration::minutes(10)));
+        assert_eq!(parse_duration("2h"), Some(Duration::hours(2)));
+        assert_eq!(parse_duration("7d"), Some(Duration::days(7)));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("10w"), None);
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("-5m"), None);
+        assert_eq!(parse_duration("8d"), None);
+        assert_eq!(parse_duration("9223372036854775807d"), None);
+        assert_eq!(parse_duration("10мин"), None);
+    }
+
+    #[test]
+    fn parses_signup_arguments() {
+        let args = parse("Lab 1 30m shuffle").unwrap();
+        assert_eq!(args.name, Some("Lab 1"));
+        assert_eq!(args.duration, Duration::minutes(30));
+        assert!(args.shuffle);
+
+        let args = parse("1h").unwrap();
+        assert_eq!(args.name, None);
+        assert!(!args.shuffle);
+
+        assert!(parse("Lab shuffle").is_none());
+    }
+}
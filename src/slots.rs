@@ -0,0 +1,244 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+
+use crate::{da, schedule};
+
+/// Longest slot, in minutes.
+const MAX_SLOT_MINUTES: i32 = 24 * 60;
+/// iCalendar lines are folded after this many bytes.
+const ICS_LINE_LIMIT: usize = 75;
+
+/// Parses `[2021-10-12] 10:00[+03] 10`, the start and the slot length in minutes.
+/// Without a date the slots start today in the offset of the time.
+pub fn parse(arg: &str, now: DateTime<Utc>) -> Option<da::Slots> {
+    let args = arg.split_whitespace().collect::<Vec<_>>();
+    let (date, time, minutes) = match args.as_slice() {
+        [time, minutes] => (None, *time, *minutes),
+        [date, time, minutes] => (
+            Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?),
+            *time,
+            *minutes,
+        ),
+        _ => return None,
+    };
+    let (time, offset) = schedule::parse_time(time)?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if !(1..=MAX_SLOT_MINUTES).contains(&minutes) {
+        return None;
+    }
+
+    let date = date.unwrap_or_else(|| now.with_timezone(&offset).date().naive_local());
+    let starts_at = offset.from_local_datetime(&date.and_time(time)).single()?;
+    Some(da::Slots {
+        starts_at: starts_at.with_timezone(&Utc),
+        minutes,
+        utc_offset: offset.local_minus_utc(),
+    })
+}
+
+/// When the element at `place` is expected, in the offset the slots were given in.
+pub fn slot_start(slots: &da::Slots, place: i32) -> DateTime<FixedOffset> {
+    let start = slots.starts_at + Duration::minutes(slots.minutes as i64 * (place as i64 - 1));
+    // the offset was valid when the slots were set
+    start.with_timezone(
+        &FixedOffset::east_opt(slots.utc_offset).unwrap_or_else(|| FixedOffset::east(0)),
+    )
+}
+
+/// An iCalendar document with an event for the slot of every element.
+pub fn calendar(
+    queue: &da::Queue,
+    slots: &da::Slots,
+    queue_elems: &[da::QueueElementForQueue],
+    now: DateTime<Utc>,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//queue-tg-bot//EN".to_string(),
+    ];
+    for elem in queue_elems {
+        let start = slot_start(slots, elem.queue_place);
+        let end = start + Duration::minutes(slots.minutes as i64);
+        let summary = match &queue.qname {
+            Some(qname) => format!("{}: {}", qname, elem.element_name),
+            None => elem.element_name.clone(),
+        };
+        lines.extend(vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}-{}@queue-tg-bot", queue.id, elem.id),
+            format!("DTSTAMP:{}", ics_time(now)),
+            format!("DTSTART:{}", ics_time(start.with_timezone(&Utc))),
+            format!("DTEND:{}", ics_time(end.with_timezone(&Utc))),
+            format!("SUMMARY:{}", ics_escape(&summary)),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in lines {
+        ics.push_str(&ics_fold(&line));
+        ics.push_str("\r\n");
+    }
+    ics
+}
+
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Breaks a long line into ones continued with a leading space, never inside a character.
+fn ics_fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > ICS_LINE_LIMIT {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(starts_at: DateTime<Utc>, minutes: i32, utc_offset: i32) -> da::Slots {
+        da::Slots {
+            starts_at,
+            minutes,
+            utc_offset,
+        }
+    }
+
+    fn unfold(ics: &str) -> String {
+        ics.replace("\r\n ", "")
+    }
+
+    #[test]
+    fn parses_a_start_today_in_the_offset_of_the_time() {
+        // it is already the 13th at +03
+        let now = Utc.ymd(2021, 10, 12).and_hms(22, 30, 0);
+        assert_eq!(
+            parse("10:00+03 15", now),
+            Some(slots(Utc.ymd(2021, 10, 13).and_hms(7, 0, 0), 15, 3 * 3600))
+        );
+        assert_eq!(
+            parse("10:00 15", now),
+            Some(slots(Utc.ymd(2021, 10, 12).and_hms(10, 0, 0), 15, 0))
+        );
+    }
+
+    #[test]
+    fn parses_a_start_date() {
+        let now = Utc.ymd(2021, 10, 1).and_hms(0, 0, 0);
+        assert_eq!(
+            parse("2021-10-12 10:00-05:30 20", now),
+            Some(slots(
+                Utc.ymd(2021, 10, 12).and_hms(15, 30, 0),
+                20,
+                -(5 * 3600 + 30 * 60)
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_slots() {
+        let now = Utc.ymd(2021, 10, 1).and_hms(0, 0, 0);
+        assert_eq!(parse("10:00 0", now), None);
+        assert_eq!(parse("10:00 1441", now), None);
+        assert_eq!(parse("10:00", now), None);
+        assert_eq!(parse("2021-13-01 10:00 15", now), None);
+        assert_eq!(parse("2021-10-12 10:00 15 extra", now), None);
+    }
+
+    #[test]
+    fn slots_start_one_after_another_in_their_offset() {
+        let slots = slots(Utc.ymd(2021, 10, 12).and_hms(7, 0, 0), 15, 3 * 3600);
+        let start = slot_start(&slots, 3);
+        assert_eq!(start.to_rfc3339(), "2021-10-12T10:30:00+03:00");
+    }
+
+    #[test]
+    fn escapes_text() {
+        assert_eq!(ics_escape("a,b;c\\d\ne"), r"a\,b\;c\\d\ne");
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let line = "a".repeat(ICS_LINE_LIMIT);
+        assert_eq!(ics_fold(&line), line);
+
+        let line = "a".repeat(ICS_LINE_LIMIT + 1);
+        assert_eq!(
+            ics_fold(&line),
+            format!("{}\r\n a", "a".repeat(ICS_LINE_LIMIT))
+        );
+    }
+
+    #[test]
+    fn never_folds_inside_a_character() {
+        // the two bytes of the last letter would end at the 76th octet
+        let line = format!("{}є{}", "a".repeat(ICS_LINE_LIMIT - 1), "є".repeat(100));
+        let folded = ics_fold(&line);
+        let lines = folded.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0], "a".repeat(ICS_LINE_LIMIT - 1));
+        assert!(lines.iter().all(|x| x.len() <= ICS_LINE_LIMIT));
+        assert!(lines[1..].iter().all(|x| x.starts_with(' ')));
+        assert_eq!(unfold(&folded), line);
+    }
+
+    #[test]
+    fn calendar_has_an_event_per_element() {
+        let now = Utc.ymd(2021, 10, 1).and_hms(0, 0, 0);
+        let starts_at = Utc.ymd(2021, 10, 12).and_hms(7, 0, 0);
+        let queue = da::Queue {
+            id: 7,
+            chat_id: -1,
+            qname: Some("Lab, part 1".to_string()),
+            version: 1,
+            created_at: now,
+            archived: false,
+            updated_at: now,
+            starts_at: Some(starts_at),
+            slot_minutes: Some(15),
+            utc_offset: 3 * 3600,
+            sheet: false,
+        };
+        let elements = ["Ann", &"Довге ім'я ".repeat(10)]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| da::QueueElementForQueue {
+                element_name: name.to_string(),
+                queue_place: i as i32 + 1,
+                id: i as i64 + 1,
+                status: da::ElementStatus::Waiting,
+                user_id: None,
+                label: None,
+            })
+            .collect::<Vec<_>>();
+
+        let ics = calendar(&queue, &queue.slots().unwrap(), &elements, now);
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.split("\r\n").all(|x| x.len() <= ICS_LINE_LIMIT));
+        assert!(!ics.replace("\r\n", "").contains('\n'));
+
+        let ics = unfold(&ics);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("UID:7-1@queue-tg-bot\r\n"));
+        assert!(ics.contains("DTSTAMP:20211001T000000Z\r\n"));
+        assert!(ics.contains("DTSTART:20211012T071500Z\r\n"));
+        assert!(ics.contains("DTEND:20211012T073000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Lab\\, part 1: Ann\r\n"));
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Duration, Utc};
+use teloxide::prelude::*;
+
+use crate::{da, error, i18n, i18n::Msg};
+
+/// How often requests to trade places are checked for expiry.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// How long the other user has to accept a request of `/trade`.
+pub const REQUEST_MINUTES: i64 = 15;
+
+pub fn expires_at(now: DateTime<Utc>) -> DateTime<Utc> {
+    now + Duration::minutes(REQUEST_MINUTES)
+}
+
+/// Checks every minute for trade requests nobody answered in time and takes their buttons off.
+pub async fn run(bot: Bot) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = expire_requests(&bot).await {
+            log::error!("Couldn't expire trade requests: {}", e);
+        }
+    }
+}
+
+async fn expire_requests(bot: &Bot) -> error::Result<()> {
+    let repo = da::QueueRepository::from_connection(crate::establish_connection());
+    // deleted first, so the buttons stop working even if the message can't be edited
+    for request in repo.take_expired_swap_requests()? {
+        let message_id = match request.message_id {
+            Some(message_id) => message_id,
+            None => continue,
+        };
+        let settings = repo.get_settings(request.chat_id)?;
+        let lang = i18n::resolve(settings.language, None);
+        let res = bot
+            .edit_message_text(
+                request.chat_id,
+                message_id as i32,
+                Msg::SwapRequestExpired.text(lang),
+            )
+            .send()
+            .await;
+        if let Err(e) = res {
+            log::error!("Couldn't expire trade request {}: {}", request.id, e);
+        }
+    }
+    Ok(())
+}